@@ -0,0 +1,87 @@
+use crate::{Interval, Point3, Ray};
+
+/// An axis-aligned bounding box.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Aabb {
+    pub x: Interval,
+    pub y: Interval,
+    pub z: Interval,
+}
+
+impl Aabb {
+    pub fn new(x: Interval, y: Interval, z: Interval) -> Self {
+        Self { x, y, z }
+    }
+
+    /// Treat the two points `a` and `b` as extrema for the bounding box, so we don't require a
+    /// particular minimum/maximum coordinate order.
+    pub fn from_points(a: Point3, b: Point3) -> Self {
+        Self {
+            x: Interval::from(a.x().min(b.x()), a.x().max(b.x())),
+            y: Interval::from(a.y().min(b.y()), a.y().max(b.y())),
+            z: Interval::from(a.z().min(b.z()), a.z().max(b.z())),
+        }
+    }
+
+    /// The smallest box enclosing both `a` and `b`.
+    pub fn enclosing(a: Aabb, b: Aabb) -> Self {
+        Self {
+            x: Interval::enclosing(a.x, b.x),
+            y: Interval::enclosing(a.y, b.y),
+            z: Interval::enclosing(a.z, b.z),
+        }
+    }
+
+    /// Widen every side thinner than a small minimum, so the slab test still hits flat boxes.
+    pub fn pad_to_minimums(self) -> Self {
+        const DELTA: f64 = 0.0001;
+        let pad = |interval: Interval| {
+            if interval.size() < DELTA {
+                interval.expand(DELTA)
+            } else {
+                interval
+            }
+        };
+        Self {
+            x: pad(self.x),
+            y: pad(self.y),
+            z: pad(self.z),
+        }
+    }
+
+    pub fn axis(&self, n: usize) -> Interval {
+        match n {
+            1 => self.y,
+            2 => self.z,
+            _ => self.x,
+        }
+    }
+
+    /// Clip `ray_t` to the part of the ray that lies inside the box, using the slab method.
+    /// Returns `None` if the ray misses the box within `ray_t`.
+    pub fn hit(&self, r: &Ray, mut ray_t: Interval) -> Option<Interval> {
+        for a in 0..3 {
+            let inv_d = 1. / r.direction()[a];
+            let orig = r.origin()[a];
+
+            let mut t0 = (self.axis(a).min - orig) * inv_d;
+            let mut t1 = (self.axis(a).max - orig) * inv_d;
+
+            if inv_d < 0. {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            if t0 > ray_t.min {
+                ray_t.min = t0;
+            }
+            if t1 < ray_t.max {
+                ray_t.max = t1;
+            }
+
+            if ray_t.max <= ray_t.min {
+                return None;
+            }
+        }
+        Some(ray_t)
+    }
+}
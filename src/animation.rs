@@ -0,0 +1,132 @@
+use crate::{Camera, Point3, Vec3};
+
+/// The state of the camera at a given time of an animation.
+#[derive(Copy, Clone, Debug)]
+pub struct Keyframe {
+    pub time: f64,        // Time of the keyframe, in seconds
+    pub lookfrom: Point3, // Point camera is looking from
+    pub lookat: Point3,   // Point camera is looking at
+    pub vup: Vec3,        // Camera-relative "up" direction
+    pub vfov: f64,        // Vertical view angle (field of view)
+    pub focus_dist: f64,  // Distance from camera lookfrom point to plane of perfect focus
+}
+
+impl Keyframe {
+    /// Capture the current settings of `camera` at `time`.
+    pub fn from_camera(time: f64, camera: &Camera) -> Self {
+        Self {
+            time,
+            lookfrom: camera.lookfrom,
+            lookat: camera.lookat,
+            vup: camera.vup,
+            vfov: camera.vfov,
+            focus_dist: camera.focus_dist,
+        }
+    }
+}
+
+/// A camera motion going smoothly through a list of keyframes.
+///
+/// Between two keyframes every parameter follows a Catmull-Rom spline, so the camera passes
+/// exactly through each keyframe without any sudden change of speed. Before the first and after
+/// the last keyframe the camera holds still.
+#[derive(Clone, Debug, Default)]
+pub struct CameraPath {
+    keyframes: Vec<Keyframe>,
+}
+
+impl CameraPath {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, keyframe: Keyframe) {
+        let index = self.keyframes.partition_point(|k| k.time <= keyframe.time);
+        self.keyframes.insert(index, keyframe);
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    /// Interpolate the keyframes at `time`, or return `None` if the path is empty.
+    pub fn at(&self, time: f64) -> Option<Keyframe> {
+        let first = self.keyframes.first()?;
+        let last = self.keyframes.last()?;
+        if time <= first.time {
+            return Some(*first);
+        }
+        if time >= last.time {
+            return Some(*last);
+        }
+
+        // Find the segment k1..k2 containing `time`, and its neighbours k0 and k3.
+        let i = self.keyframes.partition_point(|k| k.time <= time);
+        let k1 = &self.keyframes[i - 1];
+        let k2 = &self.keyframes[i];
+        let k0 = &self.keyframes[i.saturating_sub(2)];
+        let k3 = &self.keyframes[(i + 1).min(self.keyframes.len() - 1)];
+
+        let spline = Spline::new([k0.time, k1.time, k2.time, k3.time], time);
+        Some(Keyframe {
+            time,
+            lookfrom: spline.vec([k0.lookfrom, k1.lookfrom, k2.lookfrom, k3.lookfrom]),
+            lookat: spline.vec([k0.lookat, k1.lookat, k2.lookat, k3.lookat]),
+            vup: spline.vec([k0.vup, k1.vup, k2.vup, k3.vup]).unit_vector(),
+            vfov: spline.scalar([k0.vfov, k1.vfov, k2.vfov, k3.vfov]),
+            focus_dist: spline.scalar([k0.focus_dist, k1.focus_dist, k2.focus_dist, k3.focus_dist]),
+        })
+    }
+
+    /// Move `camera` to where the path is at `time`.
+    pub fn apply(&self, camera: &mut Camera, time: f64) {
+        if let Some(keyframe) = self.at(time) {
+            camera.lookfrom = keyframe.lookfrom;
+            camera.lookat = keyframe.lookat;
+            camera.vup = keyframe.vup;
+            camera.vfov = keyframe.vfov;
+            camera.focus_dist = keyframe.focus_dist;
+        }
+    }
+}
+
+/// The weights of a Catmull-Rom spline through four keyframes, evaluated at some time between
+/// the two middle ones. Since keyframes aren't evenly spaced in time, the tangents are divided
+/// by the time between the neighbours of each keyframe.
+struct Spline {
+    weights: [f64; 4],
+}
+
+impl Spline {
+    fn new([t0, t1, t2, t3]: [f64; 4], time: f64) -> Self {
+        let dt = t2 - t1;
+        let s = (time - t1) / dt;
+
+        // Cubic Hermite basis.
+        let h00 = 2. * s.powi(3) - 3. * s.powi(2) + 1.;
+        let h10 = s.powi(3) - 2. * s.powi(2) + s;
+        let h01 = -2. * s.powi(3) + 3. * s.powi(2);
+        let h11 = s.powi(3) - s.powi(2);
+
+        // The tangent at k1 is (p2 - p0) / (t2 - t0) and the one at k2 is (p3 - p1) / (t3 - t1),
+        // both scaled by the segment duration. At the ends of the path k0 = k1 or k3 = k2, which
+        // makes the tangent point along the segment itself.
+        let m1 = dt / (t2 - t0);
+        let m2 = dt / (t3 - t1);
+
+        Self {
+            weights: [-h10 * m1, h00 - h11 * m2, h01 + h10 * m1, h11 * m2],
+        }
+    }
+
+    fn scalar(&self, values: [f64; 4]) -> f64 {
+        values.iter().zip(self.weights).map(|(v, w)| v * w).sum()
+    }
+
+    fn vec(&self, values: [Vec3; 4]) -> Vec3 {
+        values
+            .iter()
+            .zip(self.weights)
+            .fold(Vec3::new(), |acc, (v, w)| acc + *v * w)
+    }
+}
@@ -0,0 +1,106 @@
+use std::{f64::consts::PI, io, path::Path, sync::Arc};
+
+use rand::Rng;
+
+use crate::{Image, Vec3};
+
+/// The shape of the camera aperture, which gives its shape to out of focus highlights.
+#[derive(Clone, Debug, Default)]
+pub enum Aperture {
+    /// A perfectly round aperture.
+    #[default]
+    Circular,
+    /// A regular polygon inscribed in the unit circle, as formed by the blades of a diaphragm.
+    /// `rotation` turns the polygon by that many degrees.
+    Polygonal { blades: usize, rotation: f64 },
+    /// An arbitrary shape given by an image.
+    Mask(Arc<ApertureMask>),
+}
+
+impl Aperture {
+    /// Return a uniformly distributed random point on the aperture, in a unit disk of the
+    /// z = 0 plane (or the unit square for masks).
+    pub fn sample(&self) -> Vec3 {
+        match self {
+            Aperture::Circular => Vec3::random_in_unit_disk(),
+            Aperture::Polygonal { blades, rotation } => {
+                let blades = (*blades).max(3);
+                let mut rng = rand::thread_rng();
+
+                // All the triangles between the center and a side of the polygon have the same
+                // area, so pick one of them uniformly then pick a point uniformly inside it.
+                let side = rng.gen_range(0..blades) as f64;
+                let step = 2. * PI / blades as f64;
+                let a0 = rotation.to_radians() + side * step;
+                let a1 = a0 + step;
+
+                let su = rng.gen::<f64>().sqrt();
+                let v = rng.gen::<f64>();
+                let (b1, b2) = (su * (1. - v), su * v);
+                Vec3::from(
+                    b1 * a0.cos() + b2 * a1.cos(),
+                    b1 * a0.sin() + b2 * a1.sin(),
+                    0,
+                )
+            }
+            Aperture::Mask(mask) => mask.sample(),
+        }
+    }
+}
+
+/// An aperture shape loaded from a grayscale image, where white lets light through and black
+/// blocks it. The image is centered on the lens and its largest side spans the lens diameter.
+#[derive(Clone, Debug)]
+pub struct ApertureMask {
+    width: usize,
+    height: usize,
+    cdf: Vec<f64>, // Cumulated transmission of the pixels, in row-major order
+}
+
+impl ApertureMask {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_image(&Image::load(path)?)
+    }
+
+    pub fn from_image(image: &Image) -> io::Result<Self> {
+        let (width, height) = (image.width(), image.height());
+        let mut total = 0.;
+        let cdf: Vec<f64> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                total += image.luminance(x, y);
+                total
+            })
+            .collect();
+
+        if total <= 0. {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "an aperture mask needs at least one non-black pixel",
+            ));
+        }
+
+        Ok(Self { width, height, cdf })
+    }
+
+    fn sample(&self) -> Vec3 {
+        let mut rng = rand::thread_rng();
+
+        // Pick a pixel proportionally to the light it lets through, then a point inside it.
+        let total = self.cdf[self.cdf.len() - 1];
+        let target = rng.gen::<f64>() * total;
+        let index = self
+            .cdf
+            .partition_point(|&c| c <= target)
+            .min(self.cdf.len() - 1);
+        let x = (index % self.width) as f64 + rng.gen::<f64>();
+        let y = (index / self.width) as f64 + rng.gen::<f64>();
+
+        let size = self.width.max(self.height) as f64;
+        Vec3::from(
+            (2. * x - self.width as f64) / size,
+            (self.height as f64 - 2. * y) / size,
+            0,
+        )
+    }
+}
@@ -1,6 +1,77 @@
+use std::{
+    f64::consts::PI,
+    fs::File,
+    io::{self, BufWriter, Write},
+    ops::Range,
+    path::Path,
+    sync::Arc,
+};
+
 use rand::Rng;
 
-use crate::{write_color, Color, HitRecord, Hittable, Interval, Point3, Ray, Vec3};
+use crate::{
+    random_float, spectrum::HeroWavelengths, write_color, Aperture, CameraPath, Color, Hittable,
+    Integrator, Interval, LensSystem, Light, PhysicalCamera, Point3, Ray, Scene, Vec3,
+};
+
+/// How the camera maps the pixels of the image to rays.
+#[derive(Clone, Debug, Default)]
+pub enum Projection {
+    /// Rays diverge from the camera center through a viewport spanning `vfov`.
+    #[default]
+    Perspective,
+    /// Rays are parallel and start across a viewport `view_width` units wide.
+    Orthographic { view_width: f64 },
+    /// A full 360x180 degrees panorama in latitude/longitude layout, looking at `lookat` in the
+    /// middle of the image. The image is always twice as wide as it is high.
+    Equirectangular,
+    /// A circular fisheye image covering `fov` degrees across the largest circle fitting in the
+    /// image. Pixels outside of that circle are left black.
+    Fisheye { fov: f64, mapping: FisheyeMapping },
+    /// The six faces of a cube around the camera, laid out in a 3x2 grid as +X, -X, +Y on the
+    /// first row and -Y, +Z, -Z on the second one, where X points right, Y up and Z backward.
+    /// Each face shows what a 90 degrees perspective camera looking along its axis would see,
+    /// with Y up on the side faces, -Z up on the +Y face and +Z up on the -Y face, so the -Z
+    /// face matches the view of the camera itself. The image is always 3/2 times wider than it
+    /// is high.
+    Cubemap,
+    /// Rays start on the sensor and go through every element of a real lens, which is moved
+    /// away from the sensor to focus at `focus_dist`.
+    Lens(Arc<LensSystem>),
+}
+
+/// How a fisheye lens maps the angle from the view direction to the distance from the center
+/// of the image.
+#[derive(Copy, Clone, Debug, Default)]
+pub enum FisheyeMapping {
+    /// The distance is proportional to the angle.
+    #[default]
+    Equidistant,
+    /// Every pixel covers the same solid angle.
+    Equisolid,
+}
+
+/// A pair of eyes looking at the scene, to render images for stereoscopic displays.
+#[derive(Copy, Clone, Debug)]
+pub struct Stereo {
+    /// Distance between the two eyes.
+    pub interocular: f64,
+    /// Distance from the camera at which both eyes see the same image. Objects closer than
+    /// that appear in front of the screen. Use `f64::INFINITY` for parallel eyes.
+    pub convergence: f64,
+    /// How the two eye images are packed in the output image.
+    pub layout: StereoLayout,
+}
+
+/// Where the left and right eye images go in a stereo image.
+#[derive(Copy, Clone, Debug, Default)]
+pub enum StereoLayout {
+    /// The left eye image on the left, the right eye image on the right.
+    #[default]
+    SideBySide,
+    /// The left eye image on top, the right eye image on the bottom.
+    TopBottom,
+}
 
 #[derive(Default)]
 pub struct Camera {
@@ -14,6 +85,16 @@ pub struct Camera {
     pub vup: Vec3,                // Camera-relative "up" direction
     pub defocus_angle: f64,       // Variation angle of rays through each pixel
     pub focus_dist: f64,          // Distance from camera lookfrom point to plane of perfect focus
+    pub shutter_open: f64,        // Time at which the shutter opens, clamped to [0, 1]
+    pub shutter_close: f64,       // Time at which the shutter closes, unless `physical` sets it
+    pub projection: Projection,   // Mapping of the image pixels to rays
+    pub stereo: Option<Stereo>,   // Render an image per eye instead of a single one
+    pub aperture: Aperture,       // Shape of the defocus disk
+    pub spectral: bool,           // Trace a few wavelengths per path instead of RGB colors
+
+    pub physical: Option<PhysicalCamera>, // Derive the settings and exposure from a real camera
+    pub lights: Vec<Arc<dyn Light>>,      // Light sources on top of the emissive objects
+    pub integrator: Box<dyn Integrator>,  // Algorithm computing the light along camera rays
 
     image_height: usize, // Rendered image height
     center: Point3,      // Camera center
@@ -24,8 +105,12 @@ pub struct Camera {
     v: Vec3,             // Camera frame basis vectors
     w: Vec3,             // Camera frame basis vectors
 
+    sensor_distance: f64, // Distance between the sensor and the rear element of the lens
+    defocus_radius: f64,  // Radius of the defocus disk
     defocus_disk_u: Vec3, // Defocus disk horizontal radius
     defocus_disk_v: Vec3, // Defocus disk vertical radius
+    shutter: Interval,    // Time range during which the shutter is open
+    exposure: f64,        // Scale applied to the pixel colors
 }
 
 impl Camera {
@@ -38,6 +123,8 @@ impl Camera {
             vfov: 90.,
             defocus_angle: 0.,
             focus_dist: 10.,
+            shutter_open: 0.,
+            shutter_close: 1.,
             lookfrom: Point3::from(0, 0, -1),
             lookat: Point3::from(0, 0, 0),
             vup: Vec3::from(0, 1, 0),
@@ -46,37 +133,111 @@ impl Camera {
     }
 
     pub fn render(&mut self, world: &impl Hittable) {
-        self.initialize();
+        let stdout = io::stdout();
+        self.render_to(world, &mut stdout.lock())
+            .expect("could not render the image to stdout");
+    }
+
+    /// Render the scene as a PPM image into `out`.
+    pub fn render_to(&mut self, world: &impl Hittable, out: &mut impl Write) -> io::Result<()> {
+        self.initialize()?;
 
-        println!("P3");
-        println!("{} {}", self.image_width, self.image_height);
-        println!("255");
+        let scene = Scene::new(world, &self.lights);
+
+        // With a stereo rig the eye images are laid out next to each other in the output.
+        let (output_width, output_height) = match self.stereo {
+            None => (self.image_width, self.image_height),
+            Some(Stereo {
+                layout: StereoLayout::SideBySide,
+                ..
+            }) => (2 * self.image_width, self.image_height),
+            Some(Stereo {
+                layout: StereoLayout::TopBottom,
+                ..
+            }) => (self.image_width, 2 * self.image_height),
+        };
+
+        writeln!(out, "P3")?;
+        writeln!(out, "{} {}", output_width, output_height)?;
+        writeln!(out, "255")?;
+
+        for y in 0..output_height {
+            eprintln!("Scanline remaining: {}", output_height - y);
+            for x in 0..output_width {
+                // Find which eye the output pixel belongs to, and where it is in that eye image.
+                let (eye, i, j) = if x >= self.image_width {
+                    (1, x - self.image_width, y)
+                } else if y >= self.image_height {
+                    (1, x, y - self.image_height)
+                } else {
+                    (0, x, y)
+                };
+                let eye_offset = self
+                    .stereo
+                    .map_or(0., |stereo| (eye as f64 - 0.5) * stereo.interocular);
 
-        for j in 0..self.image_height {
-            eprintln!("Scanline remaining: {}", self.image_height - j);
-            for i in 0..self.image_width {
                 let mut pixel_color = Color::from(0, 0, 0);
                 for _sample in 0..self.samples_per_pixel {
-                    let r = self.get_ray(i, j);
-                    pixel_color += self.ray_color(&r, self.max_depth, world);
+                    if let Some((r, weight)) = self.get_ray(eye_offset, i, j) {
+                        pixel_color += weight * self.sample_color(r, &scene);
+                    }
                 }
-                write_color(pixel_color, self.samples_per_pixel);
+                write_color(out, self.exposure * pixel_color, self.samples_per_pixel)?;
             }
         }
         eprintln!("done");
+        out.flush()
     }
 
-    fn initialize(&mut self) {
-        self.image_height = (self.image_width as f64 / self.aspect_ratio as f64).ceil() as usize;
+    /// Render the `frames` of an animation where the camera follows `path`, writing one
+    /// numbered PPM image per frame (`0000.ppm`, `0001.ppm`, ...) into the `output` directory.
+    /// Frame `n` shows the camera at `n / fps` seconds along the path.
+    pub fn render_sequence(
+        &mut self,
+        world: &impl Hittable,
+        path: &CameraPath,
+        frames: Range<usize>,
+        fps: f64,
+        output: impl AsRef<Path>,
+    ) -> io::Result<()> {
+        std::fs::create_dir_all(&output)?;
+        for frame in frames {
+            eprintln!("Rendering frame {frame}");
+            path.apply(self, frame as f64 / fps);
+            let file = File::create(output.as_ref().join(format!("{frame:04}.ppm")))?;
+            self.render_to(world, &mut BufWriter::new(file))?;
+        }
+        Ok(())
+    }
+
+    fn initialize(&mut self) -> io::Result<()> {
+        self.image_height = match self.projection {
+            Projection::Equirectangular => self.image_width / 2,
+            Projection::Cubemap => self.image_width * 2 / 3,
+            _ => {
+                let aspect_ratio = self
+                    .physical
+                    .map_or(self.aspect_ratio, |p| p.aspect_ratio());
+                (self.image_width as f64 / aspect_ratio).ceil() as usize
+            }
+        };
 
         self.center = self.lookfrom;
 
         // Determine viewport dimensions.
-        let theta = self.vfov.to_radians();
-        let h = (theta / 2.).tan();
-        let viewport_height = 2. * h * self.focus_dist;
-
-        let viewport_width = viewport_height * (self.image_width as f64 / self.image_height as f64);
+        let image_ratio = self.image_width as f64 / self.image_height as f64;
+        let (viewport_width, viewport_height) = match self.projection {
+            Projection::Perspective => {
+                let vfov = self.physical.map_or(self.vfov, |p| p.vfov());
+                let theta = vfov.to_radians();
+                let h = (theta / 2.).tan();
+                let viewport_height = 2. * h * self.focus_dist;
+                (viewport_height * image_ratio, viewport_height)
+            }
+            Projection::Orthographic { view_width } => (view_width, view_width / image_ratio),
+            // The panoramic projections don't go through a viewport.
+            _ => (0., 0.),
+        };
 
         // Calculate the u,v,w unit basis vectors for the camera coordinate frame.
         self.w = (self.lookfrom - self.lookat).unit_vector();
@@ -98,61 +259,237 @@ impl Camera {
         self.pixel00_loc = viewport_upper_left + 0.5 * (self.pixel_delta_u + self.pixel_delta_v);
 
         // Calculate the camera defocus disk basis vectors.
-        let defocus_radius = self.focus_dist * (self.defocus_angle / 2.).to_radians().tan();
-        self.defocus_disk_u = self.u * defocus_radius;
-        self.defocus_disk_v = self.v * defocus_radius;
+        self.defocus_radius = match self.physical {
+            Some(physical) => physical.aperture_radius(),
+            None => self.focus_dist * (self.defocus_angle / 2.).to_radians().tan(),
+        };
+        self.defocus_disk_u = self.u * self.defocus_radius;
+        self.defocus_disk_v = self.v * self.defocus_radius;
+
+        // A real camera keeps the shutter open for its shutter time, and the amount of light
+        // reaching the sensor depends on its settings.
+        let shutter_close = match self.physical {
+            Some(physical) => self.shutter_open + physical.shutter_fraction(),
+            None => self.shutter_close,
+        };
+        // Objects only move between time 0 and time 1, which their bounding boxes cover.
+        self.shutter = Interval::from(self.shutter_open.clamp(0., 1.), shutter_close.clamp(0., 1.));
+        self.exposure = self.physical.map_or(1., |p| p.exposure());
+
+        // Autofocus the lens.
+        if let Projection::Lens(lens) = &self.projection {
+            self.sensor_distance = lens.focus(self.focus_dist).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "the lens can't focus at focus_dist, it may be closer than the lens allows",
+                )
+            })?;
+        }
+        Ok(())
     }
 
-    fn ray_color(&self, r: &Ray, depth: usize, world: &impl Hittable) -> Color {
-        // If we've exceeded the ray bounce limit, no more light is gathered.
-        if depth <= 0 {
-            return Color::new();
+    /// The RGB color of the light arriving along the camera ray `r`.
+    fn sample_color(&self, r: Ray, scene: &Scene) -> Color {
+        if !self.spectral {
+            return self.integrator.li(&r, scene, self.max_depth);
         }
+        // Follow random wavelengths, and accumulate their color on the film.
+        let hero = HeroWavelengths::sample();
+        let radiance = self
+            .integrator
+            .li(&r.with_hero_wavelengths(hero), scene, self.max_depth);
+        hero.to_rgb(radiance)
+    }
 
-        let mut rec = HitRecord::default();
-
-        if world.hit(r, Interval::from(0.001, f64::INFINITY), &mut rec) {
-            let mut scattered = Ray::default();
-            let mut attenuation = Color::new();
-            if rec
-                .material
-                .scatter(r, &rec, &mut attenuation, &mut scattered)
-            {
-                if attenuation == Color::new() {
-                    eprintln!("here at iteration {}", self.max_depth - depth);
-                }
-                return attenuation * self.ray_color(&scattered, depth - 1, world);
+    fn get_ray(&self, eye_offset: f64, i: usize, j: usize) -> Option<(Ray, f64)> {
+        // Get a randomly-sampled camera ray for the pixel at location i,j, originating from
+        // the camera defocus disk, at a random time while the shutter is open, along with the
+        // weight of its contribution to the pixel. Returns `None` when the pixel doesn't see
+        // the scene at all.
+        // The `eye_offset` moves the camera sideways, to render one of the stereo eyes.
+
+        let ray_time = self.shutter.min + random_float() * self.shutter.size();
+
+        let (ray_origin, ray_direction, weight) = match &self.projection {
+            Projection::Perspective | Projection::Orthographic { .. } => {
+                let (origin, direction) = self.viewport_ray(eye_offset, i, j);
+                (origin, direction, 1.)
             }
-            return Color::from(0, 0, 0);
-        }
+            Projection::Lens(lens) => self.lens_ray(lens, eye_offset, i, j)?,
+            _ => {
+                let (origin, direction) = self.panoramic_ray(eye_offset, i, j)?;
+                (origin, direction, 1.)
+            }
+        };
 
-        let unit_direction = r.direction().unit_vector();
-        let a = 0.5 * unit_direction.y() + 1.0;
-        (1.0 - a) * Color::from(1.0, 1.0, 1.0) + a * Color::from(0.5, 0.7, 1.0)
+        Some((Ray::with_time(ray_origin, ray_direction, ray_time), weight))
     }
 
-    fn get_ray(&self, i: usize, j: usize) -> Ray {
-        // Get a randomly-sampled camera ray for the pixel at location i,j, originating from
-        // the camera defocus disk.
+    fn viewport_ray(&self, eye_offset: f64, i: usize, j: usize) -> (Point3, Vec3) {
+        // Returns the origin and direction of a random ray going through the pixel at location
+        // i,j of the viewport.
 
         let pixel_center =
             self.pixel00_loc + (i as f64 * self.pixel_delta_u) + (j as f64 * self.pixel_delta_v);
-        let pixel_sample = pixel_center + self.pixel_sample_square();
+        let mut pixel_sample = pixel_center + self.pixel_sample_square();
+
+        // A stereo eye looks through its own viewport, shifted so that both eyes frame the same
+        // area at the convergence distance instead of toeing in.
+        if let Some(stereo) = self.stereo {
+            pixel_sample += eye_offset * (1. - self.focus_dist / stereo.convergence) * self.u;
+        }
 
-        let ray_origin = if self.defocus_angle <= 0. {
-            self.center
+        // With an orthographic projection each pixel has its own center on the camera plane,
+        // right behind the pixel on the viewport.
+        let center = match self.projection {
+            Projection::Orthographic { .. } => pixel_sample + self.focus_dist * self.w,
+            _ => self.center,
+        } + eye_offset * self.u;
+        let ray_origin = if self.defocus_radius <= 0. {
+            center
         } else {
-            self.defocus_disk_sample()
+            center + self.defocus_disk_sample()
         };
         let ray_direction = pixel_sample - ray_origin;
 
-        return Ray::new(ray_origin, ray_direction);
+        (ray_origin, ray_direction)
+    }
+
+    fn panoramic_ray(&self, eye_offset: f64, i: usize, j: usize) -> Option<(Point3, Vec3)> {
+        // Returns the origin and direction of a random ray through the pixel at location i,j
+        // for the panoramic projections, or `None` if the pixel sees nothing.
+
+        let direction = self.panoramic_direction(i, j)?;
+
+        let Some(stereo) = self.stereo else {
+            return Some((self.center, direction));
+        };
+        if !matches!(self.projection, Projection::Equirectangular) {
+            return Some((self.center + eye_offset * self.u, direction));
+        }
+
+        // Omni-directional stereo: every column of the panorama is seen by eyes turned toward
+        // it, which puts the ray origins on a circle around the camera center.
+        let right = direction.cross(self.v);
+        if right.near_zero() {
+            // Both eyes see the same thing straight up or down.
+            return Some((self.center, direction));
+        }
+        let offset = eye_offset * right.unit_vector();
+        let converged = if stereo.convergence.is_finite() {
+            stereo.convergence * direction - offset
+        } else {
+            direction
+        };
+        Some((self.center + offset, converged))
+    }
+
+    fn panoramic_direction(&self, i: usize, j: usize) -> Option<Vec3> {
+        // Returns the direction of a random ray through the pixel at location i,j for the
+        // panoramic projections, or `None` if the pixel sees nothing.
+
+        let (s, t) = self.image_sample(i, j);
+
+        match self.projection {
+            Projection::Perspective | Projection::Orthographic { .. } | Projection::Lens(_) => {
+                unreachable!("not a panoramic projection")
+            }
+            Projection::Equirectangular => {
+                let phi = (s - 0.5) * 2. * PI; // Longitude, 0 in front of the camera
+                let theta = (0.5 - t) * PI; // Latitude, positive above the horizon
+                Some(self.camera_to_world(
+                    theta.cos() * phi.sin(),
+                    theta.sin(),
+                    -theta.cos() * phi.cos(),
+                ))
+            }
+            Projection::Fisheye { fov, mapping } => {
+                // Position relative to the center of the image, in units of the image circle
+                // radius.
+                let radius = self.image_width.min(self.image_height) as f64 / 2.;
+                let x = (s - 0.5) * self.image_width as f64 / radius;
+                let y = (0.5 - t) * self.image_height as f64 / radius;
+                let r = x.hypot(y);
+                let half_fov = fov.to_radians() / 2.;
+                let theta = match mapping {
+                    FisheyeMapping::Equidistant => r * half_fov,
+                    FisheyeMapping::Equisolid => 2. * (r * (half_fov / 2.).sin()).asin(),
+                };
+                (r <= 1. && theta <= PI).then(|| {
+                    let phi = y.atan2(x);
+                    self.camera_to_world(
+                        theta.sin() * phi.cos(),
+                        theta.sin() * phi.sin(),
+                        -theta.cos(),
+                    )
+                })
+            }
+            Projection::Cubemap => {
+                let face =
+                    (s * 3.).floor().min(2.) as usize + 3 * (t * 2.).floor().min(1.) as usize;
+                // Coordinates on the face, from -1 to 1 going right and down.
+                let a = 2. * (s * 3.).fract() - 1.;
+                let b = 2. * (t * 2.).fract() - 1.;
+                // Right on each face is its axis crossed with its up direction.
+                let (x, y, z) = match face {
+                    0 => (1., -b, a),
+                    1 => (-1., -b, -a),
+                    2 => (-a, 1., b),
+                    3 => (-a, -1., -b),
+                    4 => (-a, -b, 1.),
+                    _ => (a, -b, -1.),
+                };
+                Some(self.camera_to_world(x, y, z))
+            }
+        }
+    }
+
+    fn lens_ray(
+        &self,
+        lens: &LensSystem,
+        eye_offset: f64,
+        i: usize,
+        j: usize,
+    ) -> Option<(Point3, Vec3, f64)> {
+        // Returns the origin, direction and weight of a random ray going from the pixel at
+        // location i,j of the sensor through the lens, or `None` if the lens blocks it.
+
+        // The lens flips the image, so the top left pixel is at the bottom right of the sensor.
+        let (s, t) = self.image_sample(i, j);
+        let aspect = self.image_width as f64 / self.image_height as f64;
+        let film_width = lens.film_diagonal * aspect / (1. + aspect * aspect).sqrt();
+        let film_height = film_width / aspect;
+        let film = ((0.5 - s) * film_width, (t - 0.5) * film_height);
+
+        let (origin, direction, weight) = lens.sample_ray(self.sensor_distance, film)?;
+
+        // Lens space is in millimeters, with the scene toward +z.
+        let scale = lens.units_per_meter / 1000.;
+        let origin = self.center
+            + eye_offset * self.u
+            + scale * self.camera_to_world(origin.x(), origin.y(), -origin.z());
+        let direction = self.camera_to_world(direction.x(), direction.y(), -direction.z());
+        Some((origin, direction, weight))
+    }
+
+    fn image_sample(&self, i: usize, j: usize) -> (f64, f64) {
+        // Returns a random point of the pixel at location i,j, in image coordinates going from
+        // 0 to 1 rightward and downward.
+        let mut rng = rand::thread_rng();
+        let s = (i as f64 + rng.gen::<f64>()) / self.image_width as f64;
+        let t = (j as f64 + rng.gen::<f64>()) / self.image_height as f64;
+        (s, t)
+    }
+
+    fn camera_to_world(&self, x: f64, y: f64, z: f64) -> Vec3 {
+        // Convert a vector from the camera frame (x right, y up, z backward) to world space.
+        x * self.u + y * self.v + z * self.w
     }
 
     fn defocus_disk_sample(&self) -> Vec3 {
-        // Returns a random point in the camera defocus disk.
-        let p = Vec3::random_in_unit_disk();
-        self.center + (p.x() * self.defocus_disk_u) + (p.y() * self.defocus_disk_v)
+        // Returns a random offset from the camera center within the defocus disk.
+        let p = self.aperture.sample();
+        (p.x() * self.defocus_disk_u) + (p.y() * self.defocus_disk_v)
     }
 
     fn pixel_sample_square(&self) -> Vec3 {
@@ -160,6 +497,6 @@ impl Camera {
         let mut rng = rand::thread_rng();
         let px = -0.5 + rng.gen::<f64>();
         let py = -0.5 + rng.gen::<f64>();
-        return (px * self.pixel_delta_u) + (py * self.pixel_delta_v);
+        (px * self.pixel_delta_u) + (py * self.pixel_delta_v)
     }
 }
@@ -1,10 +1,17 @@
+use std::io::{self, Write};
+
 use crate::{Color, Interval};
 
 fn linear_to_gamma(linear_component: f64) -> f64 {
-    return linear_component.sqrt();
+    // Out of gamut colors can have negative components.
+    if linear_component > 0. {
+        linear_component.sqrt()
+    } else {
+        0.
+    }
 }
 
-pub fn write_color(color: Color, sample_per_pixel: usize) {
+pub fn write_color(out: &mut impl Write, color: Color, sample_per_pixel: usize) -> io::Result<()> {
     // Divide the color by the number of samples.
     let scale = 1. / sample_per_pixel as f64;
 
@@ -17,10 +24,11 @@ pub fn write_color(color: Color, sample_per_pixel: usize) {
 
     // eprintln!("{} {} {}", color.r(), color.g(), color.b());
 
-    println!(
+    writeln!(
+        out,
         "{} {} {}",
         (256. * intensity.clamp(r)) as usize,
         (256. * intensity.clamp(g)) as usize,
         (256. * intensity.clamp(b)) as usize
-    );
+    )
 }
@@ -0,0 +1,92 @@
+//! Fraction of the light reflected at the interface between two media.
+
+use crate::Complex;
+
+/// Unpolarized reflectance of a conductor of complex relative index of refraction `eta`, for
+/// light arriving at an angle whose cosine is `cos_theta_i`.
+pub fn conductor(cos_theta_i: f64, eta: Complex) -> f64 {
+    let cos_theta_i = Complex::from(cos_theta_i.clamp(0., 1.));
+    let sin2_theta_i = Complex::from(1.) - cos_theta_i * cos_theta_i;
+    // Snell's law, with a complex angle of transmission.
+    let sin2_theta_t = sin2_theta_i / (eta * eta);
+    let cos_theta_t = (Complex::from(1.) - sin2_theta_t).sqrt();
+
+    let r_parallel = (eta * cos_theta_i - cos_theta_t) / (eta * cos_theta_i + cos_theta_t);
+    let r_perpendicular = (cos_theta_i - eta * cos_theta_t) / (cos_theta_i + eta * cos_theta_t);
+    (r_parallel.norm() + r_perpendicular.norm()) / 2.
+}
+
+/// Unpolarized reflectance of a dielectric of relative index of refraction `eta`, for light
+/// arriving at an angle whose cosine is `cos_theta_i`. A negative cosine means the light
+/// arrives from inside the dielectric.
+pub fn dielectric(cos_theta_i: f64, eta: f64) -> f64 {
+    let (cos_theta_i, eta) = if cos_theta_i < 0. {
+        (-cos_theta_i.max(-1.), 1. / eta)
+    } else {
+        (cos_theta_i.min(1.), eta)
+    };
+
+    let sin2_theta_t = (1. - cos_theta_i * cos_theta_i) / (eta * eta);
+    if sin2_theta_t >= 1. {
+        // Total internal reflection.
+        return 1.;
+    }
+    let cos_theta_t = (1. - sin2_theta_t).sqrt();
+
+    let r_parallel = (eta * cos_theta_i - cos_theta_t) / (eta * cos_theta_i + cos_theta_t);
+    let r_perpendicular = (cos_theta_i - eta * cos_theta_t) / (cos_theta_i + eta * cos_theta_t);
+    (r_parallel * r_parallel + r_perpendicular * r_perpendicular) / 2.
+}
+
+/// Reflectance of a substrate of index of refraction `eta_t` under a thin film of index
+/// `eta_film` and `thickness` nanometers, for light of `wavelength` nanometers coming from a
+/// medium of index `eta_i` at an angle whose cosine is `cos_theta_i`. The waves reflected on
+/// both sides of the film interfere, following Airy's formula.
+pub fn thin_film(
+    cos_theta_i: f64,
+    eta_i: f64,
+    eta_film: f64,
+    eta_t: Complex,
+    thickness: f64,
+    wavelength: f64,
+) -> f64 {
+    let cos1 = Complex::from(cos_theta_i.abs().min(1.));
+    let sin2_theta_i = Complex::from(1.) - cos1 * cos1;
+    let (n1, n2, n3) = (Complex::from(eta_i), Complex::from(eta_film), eta_t);
+    // Snell's law gives the angles in the film and the substrate.
+    let cos_in = |n: Complex| {
+        let ratio = n1 / n;
+        (Complex::from(1.) - ratio * ratio * sin2_theta_i).sqrt()
+    };
+    let (cos2, cos3) = (cos_in(n2), cos_in(n3));
+
+    // Amplitude reflection coefficients for each polarization.
+    let rs = |na: Complex, ca: Complex, nb: Complex, cb: Complex| {
+        (na * ca - nb * cb) / (na * ca + nb * cb)
+    };
+    let rp = |na: Complex, ca: Complex, nb: Complex, cb: Complex| {
+        (nb * ca - na * cb) / (nb * ca + na * cb)
+    };
+
+    // Phase difference accumulated by the wave going back and forth through the film.
+    let delta = 4. * std::f64::consts::PI / wavelength * thickness * (n2 * cos2);
+    let phase = (Complex::new(0., 1.) * delta).exp();
+    let airy = |r12: Complex, r23: Complex| {
+        ((r12 + r23 * phase) / (Complex::from(1.) + r12 * r23 * phase)).norm()
+    };
+    let r_s = airy(rs(n1, cos1, n2, cos2), rs(n2, cos2, n3, cos3));
+    let r_p = airy(rp(n1, cos1, n2, cos2), rp(n2, cos2, n3, cos3));
+    (r_s + r_p) / 2.
+}
+
+/// The complex index of refraction of a metal reflecting `reflectivity` of the light at normal
+/// incidence and tending toward `edge_tint` at grazing angles, after Gulbrandsen's artist
+/// friendly parameterization.
+pub fn gulbrandsen(reflectivity: f64, edge_tint: f64) -> Complex {
+    let r = reflectivity.clamp(0., 0.99);
+    let g = edge_tint.clamp(0., 1.);
+    let sqrt_r = r.sqrt();
+    let n = g * (1. - r) / (1. + r) + (1. - g) * (1. + sqrt_r) / (1. - sqrt_r);
+    let k2 = (r * (n + 1.) * (n + 1.) - (n - 1.) * (n - 1.)) / (1. - r);
+    Complex::new(n, k2.max(0.).sqrt())
+}
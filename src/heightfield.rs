@@ -0,0 +1,297 @@
+use std::{io, path::Path, sync::Arc};
+
+use crate::{
+    hittable::Hittable, image::Image, Aabb, HitRecord, Interval, Material, Point3, Ray, Vec3,
+};
+
+/// A terrain described by a regular grid of heights.
+///
+/// The grid spans `size.x()` by `size.z()` world units starting at `origin`, and every height
+/// is scaled by `size.y()`. Each cell is split into two triangles, but the normals and uvs are
+/// interpolated across the whole grid so the surface is shaded smoothly.
+pub struct Heightfield {
+    heights: Vec<f64>,
+    normals: Vec<Vec3>,
+    nx: usize,
+    nz: usize,
+    origin: Point3,
+    size: Vec3,
+    bbox: Aabb,
+    material: Arc<dyn Material>,
+}
+
+impl Heightfield {
+    /// `heights` holds `nx * nz` samples in row-major order, a row being a line of constant z.
+    pub fn new(
+        heights: Vec<f64>,
+        nx: usize,
+        nz: usize,
+        origin: Point3,
+        size: Vec3,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        assert!(
+            nx >= 2 && nz >= 2,
+            "a heightfield needs at least 2x2 samples"
+        );
+        assert_eq!(heights.len(), nx * nz, "wrong number of heights");
+
+        let (min, max) = heights
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &h| {
+                (min.min(h), max.max(h))
+            });
+        let bbox = Aabb::from_points(
+            origin + Vec3::from(0, min * size.y(), 0),
+            origin + Vec3::from(size.x(), max * size.y(), size.z()),
+        )
+        .pad_to_minimums();
+
+        let mut heightfield = Self {
+            heights,
+            normals: Vec::new(),
+            nx,
+            nz,
+            origin,
+            size,
+            bbox,
+            material,
+        };
+        heightfield.normals = (0..nz)
+            .flat_map(|k| (0..nx).map(move |i| (i, k)))
+            .map(|(i, k)| heightfield.vertex_normal(i, k))
+            .collect();
+        heightfield
+    }
+
+    /// Build a heightfield from a grayscale image, where black is at height 0 and white at
+    /// height `size.y()`. The top row of the image ends up at `origin.z()`.
+    pub fn from_image(
+        path: impl AsRef<Path>,
+        origin: Point3,
+        size: Vec3,
+        material: Arc<dyn Material>,
+    ) -> io::Result<Self> {
+        let image = Image::load(path)?;
+        let (nx, nz) = (image.width(), image.height());
+        if nx < 2 || nz < 2 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "a heightfield image needs at least 2x2 pixels",
+            ));
+        }
+        let heights = (0..nz)
+            .flat_map(|k| (0..nx).map(move |i| (i, k)))
+            .map(|(i, k)| image.luminance(i, k))
+            .collect();
+        Ok(Self::new(heights, nx, nz, origin, size, material))
+    }
+
+    fn dx(&self) -> f64 {
+        self.size.x() / (self.nx - 1) as f64
+    }
+
+    fn dz(&self) -> f64 {
+        self.size.z() / (self.nz - 1) as f64
+    }
+
+    fn height(&self, i: usize, k: usize) -> f64 {
+        self.heights[k * self.nx + i]
+    }
+
+    fn vertex(&self, i: usize, k: usize) -> Point3 {
+        self.origin
+            + Vec3::from(
+                i as f64 * self.dx(),
+                self.height(i, k) * self.size.y(),
+                k as f64 * self.dz(),
+            )
+    }
+
+    fn vertex_normal(&self, i: usize, k: usize) -> Vec3 {
+        // Central differences inside the grid, one-sided differences on its borders.
+        let (i0, i1) = (i.saturating_sub(1), (i + 1).min(self.nx - 1));
+        let (k0, k1) = (k.saturating_sub(1), (k + 1).min(self.nz - 1));
+
+        let dhdx = (self.height(i1, k) - self.height(i0, k)) * self.size.y()
+            / ((i1 - i0) as f64 * self.dx());
+        let dhdz = (self.height(i, k1) - self.height(i, k0)) * self.size.y()
+            / ((k1 - k0) as f64 * self.dz());
+
+        Vec3::from(-dhdx, 1, -dhdz).unit_vector()
+    }
+
+    /// Intersect the two triangles of the cell `i`, `k` and return the closest hit.
+    fn hit_cell(&self, r: &Ray, i: usize, k: usize, ray_t: Interval) -> Option<CellHit> {
+        // The cell's corners, as (grid x, grid z) offsets.
+        const TRIANGLES: [[(usize, usize); 3]; 2] =
+            [[(0, 0), (1, 0), (1, 1)], [(0, 0), (1, 1), (0, 1)]];
+
+        let mut closest: Option<CellHit> = None;
+        for triangle in TRIANGLES {
+            let [a, b, c] = triangle.map(|(di, dk)| (i + di, k + dk));
+            let p0 = self.vertex(a.0, a.1);
+            let e1 = self.vertex(b.0, b.1) - p0;
+            let e2 = self.vertex(c.0, c.1) - p0;
+
+            // Möller–Trumbore ray/triangle intersection.
+            let pvec = r.direction().cross(e2);
+            let det = e1.dot(pvec);
+            if det.abs() < 1e-12 {
+                continue;
+            }
+            let inv_det = 1. / det;
+            let tvec = r.origin() - p0;
+            let b1 = tvec.dot(pvec) * inv_det;
+            if !(0. ..=1.).contains(&b1) {
+                continue;
+            }
+            let qvec = tvec.cross(e1);
+            let b2 = r.direction().dot(qvec) * inv_det;
+            if b2 < 0. || b1 + b2 > 1. {
+                continue;
+            }
+            let t = e2.dot(qvec) * inv_det;
+            let max = closest.as_ref().map_or(ray_t.max, |hit| hit.t);
+            if !Interval::from(ray_t.min, max).surrounds(t) {
+                continue;
+            }
+
+            let b0 = 1. - b1 - b2;
+            let index = |(i, k): (usize, usize)| k * self.nx + i;
+            let normal = (b0 * self.normals[index(a)]
+                + b1 * self.normals[index(b)]
+                + b2 * self.normals[index(c)])
+            .unit_vector();
+            let grid_x = b0 * a.0 as f64 + b1 * b.0 as f64 + b2 * c.0 as f64;
+            let grid_z = b0 * a.1 as f64 + b1 * b.1 as f64 + b2 * c.1 as f64;
+
+            // The triangle is flat, so its edges give the derivatives along the uvs.
+            let (nu, nv) = ((self.nx - 1) as f64, (self.nz - 1) as f64);
+            let (du1, dv1) = ((b.0 - a.0) as f64 / nu, (b.1 - a.1) as f64 / nv);
+            let (du2, dv2) = ((c.0 - a.0) as f64 / nu, (c.1 - a.1) as f64 / nv);
+            let det_uv = du1 * dv2 - dv1 * du2;
+            let dpdu = (dv2 * e1 - dv1 * e2) / det_uv;
+            let dpdv = (du1 * e2 - du2 * e1) / det_uv;
+
+            let mut geometric_normal = e1.cross(e2).unit_vector();
+            if geometric_normal.dot(normal) < 0. {
+                geometric_normal = -geometric_normal;
+            }
+            closest = Some(CellHit {
+                t,
+                grid_x,
+                grid_z,
+                normal,
+                geometric_normal,
+                dpdu,
+                dpdv,
+            });
+        }
+        closest
+    }
+}
+
+/// A ray hitting one of the triangles of a cell.
+struct CellHit {
+    t: f64,
+    /// Coordinates of the hit inside the grid.
+    grid_x: f64,
+    grid_z: f64,
+    /// Normal interpolated from the vertices, for smooth shading.
+    normal: Vec3,
+    /// Normal of the triangle itself.
+    geometric_normal: Vec3,
+    dpdu: Vec3,
+    dpdv: Vec3,
+}
+
+impl Hittable for Heightfield {
+    fn hit(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        let Some(clipped) = self.bbox.hit(r, ray_t) else {
+            return false;
+        };
+
+        // Walk the cells crossed by the ray with a 2D DDA, starting from where it enters the
+        // bounding box.
+        let (dx, dz) = (self.dx(), self.dz());
+        let entry = r.at(clipped.min);
+        let last_x = (self.nx - 2) as isize;
+        let last_z = (self.nz - 2) as isize;
+        let mut i = (((entry.x() - self.origin.x()) / dx).floor() as isize).clamp(0, last_x);
+        let mut k = (((entry.z() - self.origin.z()) / dz).floor() as isize).clamp(0, last_z);
+
+        let dir = r.direction();
+        let (step_i, t_delta_x, mut t_max_x) =
+            dda_axis(dir.x(), r.origin().x() - self.origin.x(), i, dx);
+        let (step_k, t_delta_z, mut t_max_z) =
+            dda_axis(dir.z(), r.origin().z() - self.origin.z(), k, dz);
+
+        let mut t_enter = clipped.min;
+        loop {
+            let t_exit = t_max_x.min(t_max_z).min(clipped.max);
+
+            // Skip the triangle tests when the ray stays above or below the whole cell.
+            let (y0, y1) = (r.at(t_enter).y(), r.at(t_exit).y());
+            let (ui, uk) = (i as usize, k as usize);
+            let corners = [
+                self.height(ui, uk),
+                self.height(ui + 1, uk),
+                self.height(ui, uk + 1),
+                self.height(ui + 1, uk + 1),
+            ];
+            let low = self.origin.y()
+                + corners.iter().copied().fold(f64::INFINITY, f64::min) * self.size.y();
+            let high = self.origin.y()
+                + corners.iter().copied().fold(f64::NEG_INFINITY, f64::max) * self.size.y();
+
+            if y0.min(y1) <= high && y0.max(y1) >= low {
+                if let Some(hit) = self.hit_cell(r, ui, uk, ray_t) {
+                    rec.t = hit.t;
+                    rec.p = r.at(hit.t);
+                    rec.u = hit.grid_x / (self.nx - 1) as f64;
+                    rec.v = hit.grid_z / (self.nz - 1) as f64;
+                    rec.dpdu = hit.dpdu;
+                    rec.dpdv = hit.dpdv;
+                    rec.material = self.material.clone();
+                    rec.set_face_normal(r, hit.geometric_normal);
+                    rec.set_shading_normal(hit.normal);
+                    return true;
+                }
+            }
+
+            if t_exit >= clipped.max {
+                return false;
+            }
+            if t_max_x < t_max_z {
+                i += step_i;
+                t_max_x += t_delta_x;
+            } else {
+                k += step_k;
+                t_max_z += t_delta_z;
+            }
+            if i < 0 || i > last_x || k < 0 || k > last_z {
+                return false;
+            }
+            t_enter = t_exit;
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+/// Set up the DDA along one axis of the grid: returns the cell step, the ray distance needed to
+/// cross a whole cell, and the ray distance at which the ray leaves the current `cell`.
+fn dda_axis(dir: f64, local_orig: f64, cell: isize, spacing: f64) -> (isize, f64, f64) {
+    if dir > 0. {
+        let boundary = (cell + 1) as f64 * spacing;
+        (1, spacing / dir, (boundary - local_orig) / dir)
+    } else if dir < 0. {
+        let boundary = cell as f64 * spacing;
+        (-1, -spacing / dir, (boundary - local_orig) / dir)
+    } else {
+        (0, f64::INFINITY, f64::INFINITY)
+    }
+}
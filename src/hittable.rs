@@ -1,14 +1,25 @@
 use std::sync::Arc;
 
-use crate::{material, Color, Interval, Material, Point3, Ray, Vec3};
+use crate::{material, Aabb, Color, Interval, Material, MediumStack, Onb, Point3, Ray, Vec3};
 
 #[derive(Clone)]
 pub struct HitRecord {
     pub p: Point3,
+    /// Normal used for shading, which normal and bump maps may tilt away from the geometry.
     pub normal: Vec3,
+    /// Normal of the actual surface, on the same side as `normal`.
+    pub geometric_normal: Vec3,
+    /// Derivative of the position along `u`, tangent to the surface.
+    pub dpdu: Vec3,
+    /// Derivative of the position along `v`, tangent to the surface.
+    pub dpdv: Vec3,
     pub material: Arc<dyn Material>,
     pub t: f64,
+    pub u: f64,
+    pub v: f64,
     pub front_face: bool,
+    /// Media the ray was traveling through, filled in by [`Scene::hit`](crate::Scene::hit).
+    pub media: MediumStack,
 }
 
 impl Default for HitRecord {
@@ -16,9 +27,15 @@ impl Default for HitRecord {
         Self {
             p: Default::default(),
             normal: Default::default(),
+            geometric_normal: Default::default(),
+            dpdu: Default::default(),
+            dpdv: Default::default(),
             material: Arc::new(material::Lambertian::new(Color::default())),
             t: Default::default(),
+            u: Default::default(),
+            v: Default::default(),
             front_face: Default::default(),
+            media: Default::default(),
         }
     }
 }
@@ -34,9 +51,60 @@ impl HitRecord {
         } else {
             -outward_normal
         };
+        self.geometric_normal = self.normal;
+    }
+
+    /// Replace the shading normal with `outward_normal`, flipped like the geometric normal.
+    /// Must be called after `set_face_normal`.
+    pub fn set_shading_normal(&mut self, outward_normal: Vec3) {
+        self.normal = if self.front_face {
+            outward_normal
+        } else {
+            -outward_normal
+        };
+    }
+
+    /// Whether `wo` and `wi` are on the same side of the shading normal exactly when they are
+    /// on the same side of the actual surface. Where they aren't, a tilted shading normal would
+    /// let light through the surface, or reflect it off the back.
+    pub fn sides_agree(&self, wo: Vec3, wi: Vec3) -> bool {
+        let shading = wo.dot(self.normal) * wi.dot(self.normal) > 0.;
+        let geometric = wo.dot(self.geometric_normal) * wi.dot(self.geometric_normal) > 0.;
+        shading == geometric
+    }
+
+    /// A basis around the shading normal whose `u` axis follows `dpdu`, so that anisotropic
+    /// materials line up with the texture coordinates.
+    pub fn shading_frame(&self) -> Onb {
+        Onb::with_tangent(self.normal, self.dpdu)
     }
 }
 
-pub trait Hittable {
+pub trait Hittable: Send + Sync {
     fn hit(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool;
+
+    /// A box enclosing the object at every time from 0 to 1. Rays never carry other times, as
+    /// the camera clamps its shutter interval to that range.
+    fn bounding_box(&self) -> Aabb;
+
+    /// Whether the object emits light and can be sampled with `random` and `pdf_value`.
+    fn is_emissive(&self) -> bool {
+        false
+    }
+
+    /// The emissive objects contained in this one, to sample them as lights.
+    fn lights(&self) -> Vec<Arc<dyn Hittable>> {
+        Vec::new()
+    }
+
+    /// Probability density, per unit solid angle, with which `random` picks `direction` from
+    /// `origin` at `time`.
+    fn pdf_value(&self, _origin: Point3, _direction: Vec3, _time: f64) -> f64 {
+        0.
+    }
+
+    /// A random direction from `origin` toward the object at `time`.
+    fn random(&self, _origin: Point3, _time: f64) -> Vec3 {
+        Vec3::from(1, 0, 0)
+    }
 }
@@ -1,10 +1,13 @@
 use std::sync::Arc;
 
-use crate::{HitRecord, Hittable, Interval, Ray};
+use rand::Rng;
+
+use crate::{Aabb, HitRecord, Hittable, Interval, Point3, Ray, Vec3};
 
 #[derive(Default)]
 pub struct HittableList {
     objects: Vec<Arc<dyn Hittable>>,
+    bbox: Aabb,
 }
 
 impl HittableList {
@@ -13,10 +16,16 @@ impl HittableList {
     }
 
     pub fn clear(&mut self) {
-        self.objects.clear()
+        self.objects.clear();
+        self.bbox = Aabb::default();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
     }
 
     pub fn add(&mut self, hittable: Arc<dyn Hittable>) {
+        self.bbox = Aabb::enclosing(self.bbox, hittable.bounding_box());
         self.objects.push(hittable)
     }
 }
@@ -37,4 +46,34 @@ impl Hittable for HittableList {
 
         hit_anything
     }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn lights(&self) -> Vec<Arc<dyn Hittable>> {
+        let mut lights = Vec::new();
+        for object in &self.objects {
+            if object.is_emissive() {
+                lights.push(object.clone());
+            } else {
+                lights.extend(object.lights());
+            }
+        }
+        lights
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3, time: f64) -> f64 {
+        // `random` picks one of the objects uniformly.
+        let weight = 1. / self.objects.len() as f64;
+        self.objects
+            .iter()
+            .map(|object| weight * object.pdf_value(origin, direction, time))
+            .sum()
+    }
+
+    fn random(&self, origin: Point3, time: f64) -> Vec3 {
+        let index = rand::thread_rng().gen_range(0..self.objects.len());
+        self.objects[index].random(origin, time)
+    }
 }
@@ -0,0 +1,140 @@
+use std::{fs, io, path::Path};
+
+use crate::Color;
+
+/// An image loaded from disk, with every channel mapped to [0,1].
+///
+/// Only the netpbm formats are supported (`P2`/`P5` grayscale and `P3`/`P6` color), since
+/// that's also what the renderer writes.
+#[derive(Clone, Debug)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+impl Image {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(&fs::read(path)?)
+    }
+
+    pub fn parse(bytes: &[u8]) -> io::Result<Self> {
+        let mut cursor = 0;
+        let magic = next_token(bytes, &mut cursor)?;
+        let (binary, channels) = match magic {
+            b"P2" => (false, 1),
+            b"P3" => (false, 3),
+            b"P5" => (true, 1),
+            b"P6" => (true, 3),
+            _ => return Err(invalid("unsupported image format, expected a netpbm file")),
+        };
+        let width = next_number(bytes, &mut cursor)?;
+        let height = next_number(bytes, &mut cursor)?;
+        let maxval = next_number(bytes, &mut cursor)?;
+        if width == 0 || height == 0 || maxval == 0 || maxval > u16::MAX as usize {
+            return Err(invalid("invalid image header"));
+        }
+
+        let count = width
+            .checked_mul(height)
+            .and_then(|pixels| pixels.checked_mul(channels))
+            .ok_or_else(|| invalid("image dimensions are too large"))?;
+        // The header can't be trusted to size the buffer: every sample takes at least a byte.
+        let mut samples = Vec::with_capacity(count.min(bytes.len()));
+        if binary {
+            // Exactly one whitespace byte separates the header from the raster.
+            cursor += 1;
+            let sample_size = if maxval < 256 { 1 } else { 2 };
+            let end = count
+                .checked_mul(sample_size)
+                .and_then(|size| size.checked_add(cursor))
+                .ok_or_else(|| invalid("image dimensions are too large"))?;
+            let raster = bytes
+                .get(cursor..end)
+                .ok_or_else(|| invalid("truncated image data"))?;
+            if sample_size == 1 {
+                samples.extend(raster.iter().map(|&b| b as usize));
+            } else {
+                samples.extend(
+                    raster
+                        .chunks_exact(2)
+                        .map(|c| u16::from_be_bytes([c[0], c[1]]) as usize),
+                );
+            }
+        } else {
+            for _ in 0..count {
+                samples.push(next_number(bytes, &mut cursor)?);
+            }
+        }
+
+        let scale = 1. / maxval as f64;
+        let pixels = samples
+            .chunks_exact(channels)
+            .map(|c| match c {
+                [l] => Color::from(*l as f64 * scale, *l as f64 * scale, *l as f64 * scale),
+                [r, g, b] => Color::from(*r as f64 * scale, *g as f64 * scale, *b as f64 * scale),
+                _ => unreachable!(),
+            })
+            .collect();
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Return the pixel at column `x` and row `y`, with row 0 at the top of the image.
+    /// Out of bounds coordinates are clamped to the closest edge.
+    pub fn pixel(&self, x: usize, y: usize) -> Color {
+        let x = x.min(self.width - 1);
+        let y = y.min(self.height - 1);
+        self.pixels[y * self.width + x]
+    }
+
+    /// Return the average of the three channels of the pixel at `x`, `y`.
+    pub fn luminance(&self, x: usize, y: usize) -> f64 {
+        let p = self.pixel(x, y);
+        (p.r() + p.g() + p.b()) / 3.
+    }
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn next_token<'a>(bytes: &'a [u8], cursor: &mut usize) -> io::Result<&'a [u8]> {
+    // Skip whitespace and `#` comments.
+    loop {
+        match bytes.get(*cursor) {
+            Some(b) if b.is_ascii_whitespace() => *cursor += 1,
+            Some(b'#') => {
+                while !matches!(bytes.get(*cursor), None | Some(b'\n')) {
+                    *cursor += 1;
+                }
+            }
+            Some(_) => break,
+            None => return Err(invalid("unexpected end of image")),
+        }
+    }
+    let start = *cursor;
+    while matches!(bytes.get(*cursor), Some(b) if !b.is_ascii_whitespace()) {
+        *cursor += 1;
+    }
+    Ok(&bytes[start..*cursor])
+}
+
+fn next_number(bytes: &[u8], cursor: &mut usize) -> io::Result<usize> {
+    std::str::from_utf8(next_token(bytes, cursor)?)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| invalid("expected a number in image"))
+}
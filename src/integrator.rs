@@ -0,0 +1,497 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+use crate::{
+    material::BsdfSample, random_float, Color, HitRecord, Hittable, HittableList, Interval, Light,
+    Onb, Point3, Ray, Vec3,
+};
+
+/// The scene as seen by an [`Integrator`].
+pub struct Scene<'a> {
+    /// Every object of the scene.
+    pub world: &'a dyn Hittable,
+    /// The emissive objects of `world`, to sample them as lights.
+    pub lights: HittableList,
+    /// Light sources that aren't part of `world`.
+    pub analytic_lights: &'a [Arc<dyn Light>],
+}
+
+impl<'a> Scene<'a> {
+    pub fn new(world: &'a dyn Hittable, analytic_lights: &'a [Arc<dyn Light>]) -> Self {
+        let mut lights = HittableList::new();
+        for light in world.lights() {
+            lights.add(light);
+        }
+        Self {
+            world,
+            lights,
+            analytic_lights,
+        }
+    }
+
+    /// Find the closest object along `r`, ignoring the hits too close to its origin that come
+    /// from floating point errors, and let its material shade it.
+    pub fn hit(&self, r: &Ray, rec: &mut HitRecord) -> bool {
+        if !self.world.hit(r, Interval::from(0.001, f64::INFINITY), rec) {
+            return false;
+        }
+        rec.media = r.media().clone();
+        rec.material.clone().shade(rec);
+        true
+    }
+
+    /// Light coming from the sky in the direction of `r`.
+    pub fn background(&self, r: &Ray) -> Color {
+        let unit_direction = r.direction().unit_vector();
+        let a = 0.5 * unit_direction.y() + 1.0;
+        r.spectrum((1.0 - a) * Color::from(1.0, 1.0, 1.0) + a * Color::from(0.5, 0.7, 1.0))
+    }
+
+    /// The fraction of the light left after traveling along `r` to the hit point `rec`, through
+    /// the medium `r` is in.
+    pub fn transmittance(&self, r: &Ray, rec: &HitRecord) -> Color {
+        self.transmittance_to(r, rec.t)
+    }
+
+    /// The fraction of the light left after traveling along `r` up to `r.at(t)`.
+    fn transmittance_to(&self, r: &Ray, t: f64) -> Color {
+        if r.media().is_empty() {
+            return Color::from(1, 1, 1);
+        }
+        r.spectrum(r.media().transmittance(t * r.direction().length()))
+    }
+
+    /// A ray leaving the surface `rec` toward `wi`, in the media on that side of the surface.
+    fn shadow_ray(&self, r: &Ray, rec: &HitRecord, wi: Vec3) -> Ray {
+        r.spawn(rec.p, wi)
+            .with_media(rec.material.media_toward(r, rec, wi))
+    }
+
+    /// Pick where the light traveling along `r` toward the hit point `rec` last interacted with
+    /// the medium `r` is in: on the surface, or when scattering inside the medium.
+    pub fn sample_medium(&self, r: &Ray, rec: &HitRecord) -> MediumInteraction {
+        let Some(medium) = r.media().current().filter(|medium| medium.is_scattering()) else {
+            return MediumInteraction::Surface {
+                weight: self.transmittance(r, rec),
+            };
+        };
+        let scattering = r.spectrum(medium.scattering);
+        let extinction = scattering + r.spectrum(medium.absorption);
+        let distance = rec.t * r.direction().length();
+
+        // Sample the distance to the next interaction with the extinction of a random channel.
+        // The density is the average over the channels, so each channel is weighted right.
+        let channel = ((3. * random_float()) as usize).min(2);
+        let free_path = if extinction[channel] > 0. {
+            -(1. - random_float()).ln() / extinction[channel]
+        } else {
+            f64::INFINITY
+        };
+        let average = |c: Color| (c.x() + c.y() + c.z()) / 3.;
+
+        if free_path < distance {
+            let transmittance = extinction.map(|sigma| (-sigma * free_path).exp());
+            MediumInteraction::Scatter {
+                point: r.origin() + free_path * r.direction().unit_vector(),
+                weight: scattering * transmittance / average(extinction * transmittance),
+            }
+        } else {
+            let transmittance = extinction.map(|sigma| (-sigma * distance).exp());
+            MediumInteraction::Surface {
+                weight: transmittance / average(transmittance),
+            }
+        }
+    }
+
+    /// Estimate the light reaching `rec` from the emissive objects and the light sources and
+    /// scattered toward the origin of `r`. Light from emissive objects is weighted against the
+    /// odds of the BSDF finding it, so it must be weighted the same way when the BSDF does.
+    pub fn direct_light(&self, r: &Ray, rec: &HitRecord) -> Color {
+        let mut color = Color::new();
+        if !self.lights.is_empty() {
+            color += self.sample_emissive_objects(r, rec);
+        }
+        color + self.sample_analytic_lights(r, rec)
+    }
+
+    fn sample_emissive_objects(&self, r: &Ray, rec: &HitRecord) -> Color {
+        // Shoot a shadow ray toward a random emissive object, and take the light from whatever
+        // it hits first.
+        let direction = self.lights.random(rec.p, r.time());
+        let shadow_ray = self.shadow_ray(r, rec, direction);
+        let mut light_rec = HitRecord::default();
+        if !self.hit(&shadow_ray, &mut light_rec) {
+            return Color::new();
+        }
+        let emitted = light_rec.material.emitted(&shadow_ray, &light_rec);
+        if emitted == Color::new() {
+            return Color::new();
+        }
+
+        let wo = -r.direction().unit_vector();
+        let wi = direction.unit_vector();
+        let f = rec.material.eval(rec, wo, wi);
+        let light_pdf = self.lights.pdf_value(rec.p, direction, r.time());
+        if f == Color::new() || light_pdf <= 0. || !rec.sides_agree(wo, wi) {
+            return Color::new();
+        }
+        let bsdf_pdf = rec.material.pdf(rec, wo, wi);
+        let weight = power_heuristic(light_pdf, bsdf_pdf);
+
+        r.spectrum(f)
+            * emitted
+            * self.transmittance(&shadow_ray, &light_rec)
+            * (wi.dot(rec.normal).abs() * weight / light_pdf)
+    }
+
+    fn sample_analytic_lights(&self, r: &Ray, rec: &HitRecord) -> Color {
+        // Light sources outside of the scene can't be found by the BSDF, so each one gets a
+        // shadow ray with full weight.
+        let wo = -r.direction().unit_vector();
+        let mut color = Color::new();
+        for light in self.analytic_lights {
+            let Some(sample) = light.sample(rec.p) else {
+                continue;
+            };
+            let f = rec.material.eval(rec, wo, sample.wi);
+            if f == Color::new() || !rec.sides_agree(wo, sample.wi) {
+                continue;
+            }
+            let shadow_ray = self.shadow_ray(r, rec, sample.wi);
+            let mut shadow_rec = HitRecord::default();
+            if self.world.hit(
+                &shadow_ray,
+                Interval::from(0.001, sample.distance - 0.001),
+                &mut shadow_rec,
+            ) {
+                continue;
+            }
+            let transmittance = self.transmittance_to(&shadow_ray, sample.distance);
+            color += r.spectrum(f)
+                * r.spectrum(sample.li)
+                * transmittance
+                * sample.wi.dot(rec.normal).abs();
+        }
+        color
+    }
+}
+
+/// Where light interacts with a medium, picked by [`Scene::sample_medium`].
+pub enum MediumInteraction {
+    /// The light comes from the surface at the end of the ray.
+    Surface { weight: Color },
+    /// The light scatters off the medium at `point` before the surface.
+    Scatter { point: Point3, weight: Color },
+}
+
+/// An algorithm computing how much light travels along a camera ray.
+pub trait Integrator: Send + Sync {
+    /// Light arriving at the origin of `r` from its direction, following at most `max_depth`
+    /// bounces.
+    fn li(&self, r: &Ray, scene: &Scene, max_depth: usize) -> Color;
+}
+
+impl Default for Box<dyn Integrator> {
+    fn default() -> Self {
+        Box::new(PathTracer::default())
+    }
+}
+
+/// Unidirectional path tracing with next event estimation.
+#[derive(Copy, Clone, Debug)]
+pub struct PathTracer {
+    /// Number of bounces after which paths carrying little light may be randomly ended. The
+    /// surviving paths carry more light to make up for it. `usize::MAX`, the default, never
+    /// ends paths early.
+    pub roulette_depth: usize,
+    /// Number of scattering events inside media after which a path ends. They are counted apart
+    /// from the bounces on surfaces, so that a dense medium doesn't use up `max_depth`. 1024 by
+    /// default.
+    pub max_scatter_depth: usize,
+}
+
+impl PathTracer {
+    pub fn new(roulette_depth: usize) -> Self {
+        Self {
+            roulette_depth,
+            max_scatter_depth: 1024,
+        }
+    }
+}
+
+impl Default for PathTracer {
+    fn default() -> Self {
+        Self::new(usize::MAX)
+    }
+}
+
+impl Integrator for PathTracer {
+    fn li(&self, r: &Ray, scene: &Scene, max_depth: usize) -> Color {
+        let mut color = Color::new();
+        let mut throughput = Color::from(1, 1, 1);
+        let mut ray = r.clone();
+        // Density with which the last bounce picked the direction of `ray`, or `None` if it
+        // came from the camera or a delta lobe, which light sampling can't account for.
+        let mut bsdf_pdf: Option<f64> = None;
+
+        // Stop gathering light once we've exceeded the ray bounce limit.
+        let mut depth = 0;
+        let mut scatter_depth = 0;
+        while depth < max_depth {
+            let mut rec = HitRecord::default();
+            if !scene.hit(&ray, &mut rec) {
+                color += throughput * scene.background(&ray);
+                break;
+            }
+            // Inside a scattering medium, the light may come from a bounce in the medium before
+            // the surface.
+            match scene.sample_medium(&ray, &rec) {
+                MediumInteraction::Surface { weight } => throughput *= weight,
+                MediumInteraction::Scatter { point, weight } => {
+                    scatter_depth += 1;
+                    if scatter_depth > self.max_scatter_depth {
+                        break;
+                    }
+                    // Scatter evenly in every direction, like light sampling can't.
+                    throughput *= weight;
+                    // Hits right at the start of a ray are ignored as they come from the surface
+                    // it leaves, but this point isn't on a surface: start that far behind it, or
+                    // the path could cross a surface right next to it without noticing.
+                    let direction = Vec3::random_unit_vector();
+                    ray = ray.spawn(point - 0.001 * direction, direction);
+                    bsdf_pdf = None;
+                    if !self.survives_roulette(depth, &mut throughput) {
+                        break;
+                    }
+                    continue;
+                }
+            }
+
+            // Emitters that light sampling could also have found only get their share of
+            // the multiple importance sampling weight.
+            let emitted = rec.material.emitted(&ray, &rec);
+            if emitted != Color::new() {
+                let weight = bsdf_pdf.map_or(1., |bsdf_pdf| {
+                    let light_pdf =
+                        scene
+                            .lights
+                            .pdf_value(ray.origin(), ray.direction(), ray.time());
+                    power_heuristic(bsdf_pdf, light_pdf)
+                });
+                color += throughput * emitted * weight;
+            }
+
+            if !rec.material.is_delta() {
+                color += throughput * scene.direct_light(&ray, &rec);
+            }
+
+            let Some(sample) = rec.material.sample(&ray, &rec) else {
+                break;
+            };
+            if !rec.sides_agree(-ray.direction(), sample.scattered.direction()) {
+                break;
+            }
+            throughput *= sample_weight(&ray, &sample, rec.normal);
+            bsdf_pdf = (!sample.is_delta).then_some(sample.pdf);
+            ray = sample.scattered;
+
+            if !self.survives_roulette(depth, &mut throughput) {
+                break;
+            }
+            depth += 1;
+        }
+
+        color
+    }
+}
+
+impl PathTracer {
+    /// Russian roulette: end the path with a probability that grows as its `throughput` drops,
+    /// and boost the survivors so the estimate stays unbiased.
+    fn survives_roulette(&self, depth: usize, throughput: &mut Color) -> bool {
+        if depth + 1 < self.roulette_depth {
+            return true;
+        }
+        let survival = throughput.max_component().min(1.);
+        if random_float() >= survival {
+            return false;
+        }
+        *throughput /= survival;
+        true
+    }
+}
+
+/// Classic recursive ray tracing: only the direct lighting is computed on rough surfaces, and
+/// only mirrors and glass send rays further.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Whitted;
+
+impl Integrator for Whitted {
+    fn li(&self, r: &Ray, scene: &Scene, max_depth: usize) -> Color {
+        if max_depth == 0 {
+            return Color::new();
+        }
+        let mut rec = HitRecord::default();
+        if !scene.hit(r, &mut rec) {
+            return scene.background(r);
+        }
+        scene.transmittance(r, &rec) * self.shade(r, &rec, scene, max_depth)
+    }
+}
+
+impl Whitted {
+    /// Light leaving the hit point `rec` toward the origin of `r`.
+    fn shade(&self, r: &Ray, rec: &HitRecord, scene: &Scene, max_depth: usize) -> Color {
+        let emitted = rec.material.emitted(r, rec);
+        if !rec.material.is_delta() {
+            // Light sampling gets the full weight since no other strategy finds the lights.
+            let mut direct = Color::new();
+            let wo = -r.direction().unit_vector();
+            if !scene.lights.is_empty() {
+                let direction = scene.lights.random(rec.p, r.time());
+                let shadow_ray = scene.shadow_ray(r, rec, direction);
+                let mut light_rec = HitRecord::default();
+                let light_pdf = scene.lights.pdf_value(rec.p, direction, r.time());
+                if light_pdf > 0.
+                    && rec.sides_agree(wo, direction)
+                    && scene.hit(&shadow_ray, &mut light_rec)
+                {
+                    let wi = direction.unit_vector();
+                    direct += r.spectrum(rec.material.eval(rec, wo, wi))
+                        * light_rec.material.emitted(&shadow_ray, &light_rec)
+                        * scene.transmittance(&shadow_ray, &light_rec)
+                        * (wi.dot(rec.normal).abs() / light_pdf);
+                }
+            }
+            return emitted + direct + scene.sample_analytic_lights(r, rec);
+        }
+
+        match rec.material.sample(r, rec) {
+            Some(sample) if rec.sides_agree(-r.direction(), sample.scattered.direction()) => {
+                emitted
+                    + sample_weight(r, &sample, rec.normal)
+                        * self.li(&sample.scattered, scene, max_depth - 1)
+            }
+            _ => emitted,
+        }
+    }
+}
+
+/// The fraction of the hemisphere above each visible point that isn't blocked by an object
+/// closer than `distance`, estimated with `samples` rays.
+#[derive(Copy, Clone, Debug)]
+pub struct AmbientOcclusion {
+    pub distance: f64,
+    pub samples: usize,
+}
+
+impl Default for AmbientOcclusion {
+    fn default() -> Self {
+        Self {
+            distance: 1.,
+            samples: 4,
+        }
+    }
+}
+
+impl Integrator for AmbientOcclusion {
+    fn li(&self, r: &Ray, scene: &Scene, _max_depth: usize) -> Color {
+        let mut rec = HitRecord::default();
+        if !scene.hit(r, &mut rec) {
+            return r.spectrum(Color::from(1, 1, 1));
+        }
+
+        // Cosine weighted directions, so that each ray counts as much as the others.
+        let uvw = Onb::new(rec.normal);
+        let mut unoccluded = 0;
+        for _ in 0..self.samples {
+            let direction = uvw.transform(Vec3::random_cosine_direction());
+            let occlusion_ray = r.spawn(rec.p, direction);
+            let mut occluder = HitRecord::default();
+            if !scene.world.hit(
+                &occlusion_ray,
+                Interval::from(0.001, self.distance),
+                &mut occluder,
+            ) {
+                unoccluded += 1;
+            }
+        }
+        let visibility = unoccluded as f64 / self.samples.max(1) as f64;
+        r.spectrum(Color::from(visibility, visibility, visibility))
+    }
+}
+
+/// Integrators showing a property of the first surface seen by each ray, to debug scenes.
+#[derive(Copy, Clone, Debug)]
+pub enum DebugView {
+    /// The normal of the surface, with each axis mapped from [-1, 1] to [0, 1].
+    Normals,
+    /// The distance to the surface, from black at the camera to white at `far`.
+    Depth { far: f64 },
+    /// The texture coordinates of the surface, in the red and green channels.
+    Uv,
+    /// A color unique to each material.
+    MaterialId,
+}
+
+impl Integrator for DebugView {
+    fn li(&self, r: &Ray, scene: &Scene, _max_depth: usize) -> Color {
+        let mut rec = HitRecord::default();
+        if !scene.hit(r, &mut rec) {
+            return match self {
+                DebugView::Depth { .. } => r.spectrum(Color::from(1, 1, 1)),
+                _ => Color::new(),
+            };
+        }
+
+        // The camera gamma corrects the colors, so undo it to show the values as they are.
+        let linear = |c: Color| r.spectrum(c * c);
+        match self {
+            DebugView::Normals => linear(0.5 * (rec.normal + Color::from(1, 1, 1))),
+            DebugView::Depth { far } => {
+                let depth = (rec.t * r.direction().length() / far).min(1.);
+                linear(Color::from(depth, depth, depth))
+            }
+            DebugView::Uv => linear(Color::from(rec.u, rec.v, 0)),
+            DebugView::MaterialId => {
+                let mut hasher = DefaultHasher::new();
+                Arc::as_ptr(&rec.material).cast::<()>().hash(&mut hasher);
+                let hash = hasher.finish();
+                let channel = |shift: u32| ((hash >> shift) & 0xff) as f64 / 255.;
+                linear(Color::from(channel(0), channel(8), channel(16)))
+            }
+        }
+    }
+}
+
+/// The factor to apply to the light coming back along the direction of `sample`, as the kind
+/// of color carried by `r`.
+fn sample_weight(r: &Ray, sample: &BsdfSample, normal: Vec3) -> Color {
+    let mut weight = sample.weight(normal);
+    if !sample.is_spectral {
+        weight = r.spectrum(weight);
+    }
+    // Once a spectral path stops following its secondary wavelengths, the hero wavelength
+    // stands for all three.
+    if let (Some(before), Some(after)) = (r.hero_wavelengths(), sample.scattered.hero_wavelengths())
+    {
+        if after.is_secondary_terminated() && !before.is_secondary_terminated() {
+            weight *= Color::from(3, 0, 0);
+        }
+    }
+    weight
+}
+
+/// Weight of a sample from a strategy with density `pdf` when another strategy could have
+/// found it with density `other_pdf`.
+fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    if a + b == 0. {
+        0.
+    } else {
+        a / (a + b)
+    }
+}
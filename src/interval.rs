@@ -6,6 +6,12 @@ pub struct Interval {
     pub max: f64,
 }
 
+impl Default for Interval {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
 impl Interval {
     pub fn new() -> Interval {
         Self::empty()
@@ -32,6 +38,26 @@ impl Interval {
         }
     }
 
+    /// The smallest interval enclosing both `a` and `b`.
+    pub fn enclosing(a: Interval, b: Interval) -> Interval {
+        Interval {
+            min: a.min.min(b.min),
+            max: a.max.max(b.max),
+        }
+    }
+
+    pub fn size(self) -> f64 {
+        self.max - self.min
+    }
+
+    pub fn expand(self, delta: f64) -> Interval {
+        let padding = delta / 2.;
+        Interval {
+            min: self.min - padding,
+            max: self.max + padding,
+        }
+    }
+
     pub fn contains(self, x: f64) -> bool {
         self.min <= x && x <= self.max
     }
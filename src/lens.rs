@@ -0,0 +1,239 @@
+use std::{fs, io, path::Path};
+
+use crate::{Point3, Vec3};
+
+/// One spherical interface of a lens, in millimeters.
+///
+/// This follows the usual lens prescription tables: elements are listed from the scene side to
+/// the sensor side, a positive radius puts the center of curvature on the sensor side, and the
+/// thickness and index of refraction describe the medium between this interface and the next.
+#[derive(Copy, Clone, Debug)]
+pub struct LensElement {
+    pub curvature_radius: f64, // Radius of the interface, 0 for the aperture stop
+    pub thickness: f64,        // Distance to the next interface along the optical axis
+    pub ior: f64,              // Index of refraction after the interface, 0 or 1 for air
+    pub aperture_radius: f64,  // Radius of the opening of the interface
+}
+
+/// A camera lens made of several spherical elements, traced ray by ray.
+///
+/// Lens space has the sensor at z = 0 and the scene toward +z.
+#[derive(Clone, Debug)]
+pub struct LensSystem {
+    elements: Vec<LensElement>,
+    pub film_diagonal: f64,   // Diagonal of the sensor, in millimeters
+    pub units_per_meter: f64, // Size of a meter in scene units
+}
+
+impl LensSystem {
+    pub fn new(elements: Vec<LensElement>, film_diagonal: f64) -> Self {
+        Self {
+            elements,
+            film_diagonal,
+            units_per_meter: 1.,
+        }
+    }
+
+    /// Load a lens prescription: one element per line with its curvature radius, thickness,
+    /// index of refraction and aperture diameter, all in millimeters. Lines starting with `#`
+    /// are comments.
+    pub fn load(path: impl AsRef<Path>, film_diagonal: f64) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?, film_diagonal)
+    }
+
+    pub fn parse(prescription: &str, film_diagonal: f64) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        let mut elements = Vec::new();
+        for line in prescription.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let values = line
+                .split_whitespace()
+                .map(|v| v.parse::<f64>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| invalid("expected numbers in the lens prescription"))?;
+            let [curvature_radius, thickness, ior, aperture_diameter] = values[..] else {
+                return Err(invalid("expected 4 values per lens element"));
+            };
+            elements.push(LensElement {
+                curvature_radius,
+                thickness,
+                ior,
+                aperture_radius: aperture_diameter / 2.,
+            });
+        }
+        if elements.is_empty() {
+            return Err(invalid("the lens prescription has no elements"));
+        }
+        Ok(Self::new(elements, film_diagonal))
+    }
+
+    pub fn elements(&self) -> &[LensElement] {
+        &self.elements
+    }
+
+    /// Distance between the rear element and the sensor that puts objects at `focus_distance`
+    /// (in scene units, from the sensor) in focus, or `None` if the lens can't focus there.
+    pub fn focus(&self, focus_distance: f64) -> Option<f64> {
+        let distance = focus_distance / self.units_per_meter * 1000.;
+
+        // Trace a ray from the on-axis object point through the lens, and find out where it
+        // crosses the axis again. Moving the lens away from the sensor moves that point by
+        // about as much, which makes the secant method converge quickly.
+        let height = 0.05 * self.elements[0].aperture_radius;
+        let image_z = |sensor_distance: f64| {
+            let front = self.vertices(sensor_distance)[0];
+            if front >= distance {
+                return None;
+            }
+            let origin = Point3::from(0, 0, distance);
+            let (o, d) = self.trace_from_scene(
+                sensor_distance,
+                origin,
+                Point3::from(height, 0, front) - origin,
+            )?;
+            (d.x() != 0.).then(|| o.z() - o.x() / d.x() * d.z())
+        };
+
+        let mut s0 = self.elements[self.elements.len() - 1].thickness.max(1.);
+        let mut g0 = image_z(s0)?;
+        let mut s1 = s0 - g0;
+        for _ in 0..50 {
+            let g1 = image_z(s1)?;
+            if g1.abs() < 1e-9 {
+                return (s1 > 0.).then_some(s1);
+            }
+            let slope = (g1 - g0) / (s1 - s0);
+            if slope == 0. || !slope.is_finite() {
+                return None;
+            }
+            (s0, g0) = (s1, g1);
+            s1 -= g1 / slope;
+        }
+        None
+    }
+
+    /// Trace a ray from the point `film` of the sensor, in millimeters from its center, toward
+    /// a random point of the rear element. Returns the origin and direction of the ray leaving
+    /// the front element in lens space, along with the weight of the sample, or `None` if the
+    /// lens blocks it.
+    pub fn sample_ray(
+        &self,
+        sensor_distance: f64,
+        film: (f64, f64),
+    ) -> Option<(Point3, Vec3, f64)> {
+        let rear = &self.elements[self.elements.len() - 1];
+        let disk = Vec3::random_in_unit_disk() * rear.aperture_radius;
+
+        let origin = Point3::from(film.0, film.1, 0);
+        let direction = Point3::from(disk.x(), disk.y(), sensor_distance) - origin;
+        let (o, d) = self.trace_from_film(sensor_distance, origin, direction)?;
+
+        // The irradiance on the sensor falls off with the fourth power of the cosine of the
+        // angle the light arrives with, which gives the natural vignetting of real lenses.
+        let cos_theta = direction.z() / direction.length();
+        Some((o, d, cos_theta.powi(4)))
+    }
+
+    /// Position of every interface along the optical axis, when the rear element is at
+    /// `sensor_distance` from the sensor.
+    fn vertices(&self, sensor_distance: f64) -> Vec<f64> {
+        let mut z = sensor_distance;
+        let mut vertices = vec![0.; self.elements.len()];
+        for i in (0..self.elements.len()).rev() {
+            vertices[i] = z;
+            if i > 0 {
+                z += self.elements[i - 1].thickness;
+            }
+        }
+        vertices
+    }
+
+    /// Index of refraction on the scene side and on the sensor side of the interface `i`.
+    fn iors(&self, i: usize) -> (f64, f64) {
+        let ior = |element: &LensElement| if element.ior == 0. { 1. } else { element.ior };
+        let before = if i == 0 {
+            1.
+        } else {
+            ior(&self.elements[i - 1])
+        };
+        (before, ior(&self.elements[i]))
+    }
+
+    fn trace_from_film(&self, sensor_distance: f64, o: Point3, d: Vec3) -> Option<(Point3, Vec3)> {
+        let vertices = self.vertices(sensor_distance);
+        (0..self.elements.len())
+            .rev()
+            .try_fold((o, d), |(o, d), i| {
+                let (scene_ior, sensor_ior) = self.iors(i);
+                self.cross_interface(i, vertices[i], o, d, sensor_ior / scene_ior)
+            })
+    }
+
+    fn trace_from_scene(&self, sensor_distance: f64, o: Point3, d: Vec3) -> Option<(Point3, Vec3)> {
+        let vertices = self.vertices(sensor_distance);
+        (0..self.elements.len()).try_fold((o, d), |(o, d), i| {
+            let (scene_ior, sensor_ior) = self.iors(i);
+            self.cross_interface(i, vertices[i], o, d, scene_ior / sensor_ior)
+        })
+    }
+
+    /// Intersect the interface `i` whose vertex is at `vertex` and refract the ray through it,
+    /// with `eta` the ratio of the incident over the transmitted index of refraction.
+    fn cross_interface(
+        &self,
+        i: usize,
+        vertex: f64,
+        o: Point3,
+        d: Vec3,
+        eta: f64,
+    ) -> Option<(Point3, Vec3)> {
+        let element = &self.elements[i];
+        let d = d.unit_vector();
+
+        if element.curvature_radius == 0. {
+            // The aperture stop only blocks light.
+            let t = (vertex - o.z()) / d.z();
+            let p = o + t * d;
+            return (t > 0. && p.x().hypot(p.y()) <= element.aperture_radius).then_some((p, d));
+        }
+
+        let radius = element.curvature_radius;
+        let center = Point3::from(0, 0, vertex - radius);
+        let oc = o - center;
+        let half_b = oc.dot(d);
+        let c = oc.length_squared() - radius * radius;
+        let discriminant = half_b * half_b - c;
+        if discriminant < 0. {
+            return None;
+        }
+
+        // Of the two intersections, only the one on the same side of the center as the vertex
+        // is part of the lens.
+        let sqrtd = discriminant.sqrt();
+        let p = [-half_b - sqrtd, -half_b + sqrtd]
+            .into_iter()
+            .filter(|&t| t > 1e-9)
+            .map(|t| o + t * d)
+            .find(|p| (p.z() - center.z()) * radius > 0.)?;
+        if p.x().hypot(p.y()) > element.aperture_radius {
+            return None;
+        }
+
+        let mut normal = (p - center) / radius.abs();
+        if normal.dot(d) > 0. {
+            normal = -normal;
+        }
+        let cos_theta = -d.dot(normal);
+        let sin2_theta_t = eta * eta * (1. - cos_theta * cos_theta);
+        if sin2_theta_t > 1. {
+            // Total internal reflection.
+            return None;
+        }
+        let refracted = eta * d + (eta * cos_theta - (1. - sin2_theta_t).sqrt()) * normal;
+        Some((p, refracted))
+    }
+}
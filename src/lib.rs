@@ -1,26 +1,60 @@
+mod aabb;
+mod animation;
+mod aperture;
 mod camera;
 mod color;
+mod complex;
+pub mod fresnel;
+mod heightfield;
 mod hittable;
 mod hittable_list;
+mod image;
+mod integrator;
 mod interval;
+mod lens;
+mod light;
 pub mod material;
+mod medium;
+mod microfacet;
+mod onb;
+mod physical_camera;
 mod ray;
+pub mod spectrum;
 mod sphere;
+pub mod texture;
+mod transform;
 mod vec;
 
 pub type Vec3 = vec::Vec<3>;
 pub type Point3 = Vec3;
 pub type Color = Vec3;
 
-pub use camera::Camera;
+pub use aabb::Aabb;
+pub use animation::{CameraPath, Keyframe};
+pub use aperture::{Aperture, ApertureMask};
+pub use camera::{Camera, FisheyeMapping, Projection, Stereo, StereoLayout};
 pub use color::write_color;
+pub use complex::Complex;
+pub use heightfield::Heightfield;
 pub use hittable::*;
 pub use hittable_list::HittableList;
+pub use image::Image;
+pub use integrator::{
+    AmbientOcclusion, DebugView, Integrator, MediumInteraction, PathTracer, Scene, Whitted,
+};
 pub use interval::Interval;
+pub use lens::{LensElement, LensSystem};
+pub use light::{DirectionalLight, Falloff, Light, LightSample, PointLight, SpotLight};
 pub use material::Material;
+pub use medium::{Medium, MediumStack};
+pub use microfacet::TrowbridgeReitz;
+pub use onb::Onb;
+pub use physical_camera::PhysicalCamera;
 use rand::Rng;
 pub use ray::Ray;
 pub use sphere::Sphere;
+pub use texture::Texture;
+pub use transform::{RotateY, Translate};
 
 /// Return a random float between 0 and 1 included.
 pub fn random_float() -> f64 {
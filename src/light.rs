@@ -0,0 +1,200 @@
+use std::f64::consts::PI;
+
+use crate::{random_float, Color, Onb, Point3, Vec3};
+
+/// Light arriving at a point from a light source, as picked by [`Light::sample`].
+#[derive(Copy, Clone, Debug)]
+pub struct LightSample {
+    /// Unit direction from the lit point toward the light.
+    pub wi: Vec3,
+    /// Distance to the light along `wi`, infinite for lights that are infinitely far.
+    pub distance: f64,
+    /// Light arriving along `wi`, divided by the probability density of picking `wi`.
+    pub li: Color,
+}
+
+/// A light source that isn't part of the scene geometry, so rays can't hit it and it can only
+/// be reached with shadow rays.
+pub trait Light: Send + Sync {
+    /// Pick a direction from `p` toward the light, or return `None` if the light doesn't
+    /// reach `p`.
+    fn sample(&self, p: Point3) -> Option<LightSample>;
+}
+
+/// How the intensity of a point or spot light decreases with distance.
+#[derive(Copy, Clone, Debug, Default)]
+pub enum Falloff {
+    /// The physically correct inverse square law.
+    #[default]
+    Quadratic,
+    /// Inversely proportional to the distance.
+    Linear,
+    /// The light doesn't get dimmer with distance.
+    Constant,
+}
+
+impl Falloff {
+    fn attenuation(self, distance: f64) -> f64 {
+        match self {
+            Falloff::Quadratic => 1. / (distance * distance),
+            Falloff::Linear => 1. / distance,
+            Falloff::Constant => 1.,
+        }
+    }
+}
+
+/// A light emitting the same intensity in every direction from a single point.
+pub struct PointLight {
+    position: Point3,
+    intensity: Color,
+    falloff: Falloff,
+}
+
+impl PointLight {
+    pub fn new(position: Point3, intensity: Color) -> Self {
+        Self::with_falloff(position, intensity, Falloff::default())
+    }
+
+    pub fn with_falloff(position: Point3, intensity: Color, falloff: Falloff) -> Self {
+        Self {
+            position,
+            intensity,
+            falloff,
+        }
+    }
+}
+
+impl Light for PointLight {
+    fn sample(&self, p: Point3) -> Option<LightSample> {
+        let to_light = self.position - p;
+        let distance = to_light.length();
+        Some(LightSample {
+            wi: to_light / distance,
+            distance,
+            li: self.intensity * self.falloff.attenuation(distance),
+        })
+    }
+}
+
+/// A point light only shining within a cone.
+pub struct SpotLight {
+    position: Point3,
+    direction: Vec3,
+    intensity: Color,
+    cos_cone: f64, // Cosine of the angle where the light stops
+    cos_full: f64, // Cosine of the angle where the light starts fading out
+    falloff: Falloff,
+}
+
+impl SpotLight {
+    /// A spot at `position` pointing toward `direction`. The light covers `cone_angle` degrees
+    /// from the axis, and fades out smoothly over the last `edge` degrees of the cone.
+    pub fn new(
+        position: Point3,
+        direction: Vec3,
+        intensity: Color,
+        cone_angle: f64,
+        edge: f64,
+    ) -> Self {
+        Self::with_falloff(
+            position,
+            direction,
+            intensity,
+            cone_angle,
+            edge,
+            Falloff::default(),
+        )
+    }
+
+    pub fn with_falloff(
+        position: Point3,
+        direction: Vec3,
+        intensity: Color,
+        cone_angle: f64,
+        edge: f64,
+        falloff: Falloff,
+    ) -> Self {
+        let edge = edge.clamp(0., cone_angle);
+        Self {
+            position,
+            direction: direction.unit_vector(),
+            intensity,
+            cos_cone: cone_angle.to_radians().cos(),
+            cos_full: (cone_angle - edge).to_radians().cos(),
+            falloff,
+        }
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, p: Point3) -> Option<LightSample> {
+        let to_light = self.position - p;
+        let distance = to_light.length();
+        let wi = to_light / distance;
+
+        let cos_theta = (-wi).dot(self.direction);
+        if cos_theta <= self.cos_cone {
+            return None;
+        }
+        let edge = if cos_theta >= self.cos_full {
+            1.
+        } else {
+            // Smoothstep between the edge of the cone and the fully lit part.
+            let t = (cos_theta - self.cos_cone) / (self.cos_full - self.cos_cone);
+            t * t * (3. - 2. * t)
+        };
+
+        Some(LightSample {
+            wi,
+            distance,
+            li: self.intensity * (edge * self.falloff.attenuation(distance)),
+        })
+    }
+}
+
+/// A light infinitely far away, like the sun.
+pub struct DirectionalLight {
+    direction: Vec3,
+    irradiance: Color,
+    cos_max: f64, // Cosine of the angular radius of the light
+}
+
+impl DirectionalLight {
+    /// A light travelling along `direction` and giving `irradiance` to surfaces facing it.
+    /// A non-zero `angular_diameter`, in degrees, makes the light a disk in the sky that
+    /// casts soft shadows, as the sun does with about 0.53 degrees.
+    pub fn new(direction: Vec3, irradiance: Color, angular_diameter: f64) -> Self {
+        Self {
+            direction: direction.unit_vector(),
+            irradiance,
+            cos_max: (angular_diameter / 2.).to_radians().cos(),
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _p: Point3) -> Option<LightSample> {
+        let to_light = -self.direction;
+        let wi = if self.cos_max < 1. {
+            // Uniformly pick a direction within the cone covered by the light's disk. The
+            // radiance of the disk is the irradiance divided by its solid angle, and the
+            // density of the direction is one over that solid angle, so they cancel out.
+            let cos_theta = 1. + random_float() * (self.cos_max - 1.);
+            let sin_theta = (1. - cos_theta * cos_theta).sqrt();
+            let phi = 2. * PI * random_float();
+            Onb::new(to_light).transform(Vec3::from(
+                sin_theta * phi.cos(),
+                sin_theta * phi.sin(),
+                cos_theta,
+            ))
+        } else {
+            to_light
+        };
+
+        Some(LightSample {
+            wi,
+            distance: f64::INFINITY,
+            li: self.irradiance,
+        })
+    }
+}
@@ -1,15 +1,93 @@
-use rand::Rng;
+use std::{
+    f64::consts::PI,
+    io,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
-use crate::{Color, HitRecord, Ray, Vec3};
+use crate::{
+    fresnel,
+    spectrum::{self, Spectrum},
+    texture::{ImageTexture, SolidColor},
+    Color, Complex, HitRecord, Image, Medium, MediumStack, Onb, Ray, Texture, TrowbridgeReitz,
+    Vec3,
+};
 
-pub trait Material {
-    fn scatter(
-        &self,
-        r_in: &Ray,
-        rec: &HitRecord,
-        attenuation: &mut Color,
-        scattered: &mut Ray,
-    ) -> bool;
+/// A direction picked by [`Material::sample`].
+#[derive(Clone)]
+pub struct BsdfSample {
+    /// The ray leaving the surface in the sampled direction.
+    pub scattered: Ray,
+    /// Value of the BSDF for the sampled direction. For a delta lobe this is directly the
+    /// fraction of the light carried along `scattered`.
+    pub f: Color,
+    /// Probability density of the sampled direction, per unit solid angle. Delta lobes use 1.
+    pub pdf: f64,
+    /// Whether the direction comes from a delta lobe, like a perfect mirror, that `eval` and
+    /// `pdf` can never reach.
+    pub is_delta: bool,
+    /// Whether `f` is already the kind of color carried by the ray, like the values of a thin
+    /// film at the hero wavelengths, instead of RGB (see [`Ray::spectrum`]).
+    pub is_spectral: bool,
+}
+
+impl BsdfSample {
+    /// The factor to apply to the light coming back along `scattered`, i.e. `f * cos / pdf`.
+    pub fn weight(&self, normal: Vec3) -> Color {
+        if self.is_delta {
+            self.f
+        } else {
+            let cos_theta = self.scattered.direction().unit_vector().dot(normal).abs();
+            self.f * cos_theta / self.pdf
+        }
+    }
+}
+
+/// How light scatters at a surface.
+///
+/// Directions are unit vectors pointing away from the surface: `wo` goes back toward where the
+/// incoming ray came from, and `wi` toward where the light comes from.
+pub trait Material: Send + Sync {
+    /// Value of the BSDF for light arriving from `wi` and leaving toward `wo`. Delta lobes are
+    /// never included.
+    fn eval(&self, rec: &HitRecord, wo: Vec3, wi: Vec3) -> Color;
+
+    /// Pick a direction to continue the path of `r_in`, or return `None` if the light is
+    /// absorbed.
+    fn sample(&self, r_in: &Ray, rec: &HitRecord) -> Option<BsdfSample>;
+
+    /// Probability density with which `sample` would pick `wi`, per unit solid angle.
+    fn pdf(&self, rec: &HitRecord, wo: Vec3, wi: Vec3) -> f64;
+
+    /// Whether every lobe of the material is a delta lobe, in which case `eval` and `pdf`
+    /// always return zero.
+    fn is_delta(&self) -> bool {
+        false
+    }
+
+    /// Light emitted by the surface toward the origin of `r_in`, as the kind of color carried by
+    /// `r_in` (see [`Ray::spectrum`]). The values of the BSDF are always RGB.
+    fn emitted(&self, _r_in: &Ray, _rec: &HitRecord) -> Color {
+        Color::new()
+    }
+
+    /// Whether the material emits any light, to sample it as a light source.
+    fn is_emissive(&self) -> bool {
+        false
+    }
+
+    /// Adjust `rec` before the other methods see it, like tilting its shading normal. Called by
+    /// [`Scene::hit`](crate::Scene::hit) on every hit.
+    fn shade(&self, _rec: &mut HitRecord) {}
+
+    /// Media a path continuing from `r_in` toward `wi` travels through, like the shadow rays
+    /// of direct lighting.
+    fn media_toward(&self, r_in: &Ray, _rec: &HitRecord, _wi: Vec3) -> MediumStack {
+        r_in.media().clone()
+    }
 }
 
 pub struct Lambertian {
@@ -23,27 +101,239 @@ impl Lambertian {
 }
 
 impl Material for Lambertian {
-    fn scatter(
-        &self,
-        _r_in: &Ray,
-        rec: &HitRecord,
-        attenuation: &mut Color,
-        scattered: &mut Ray,
-    ) -> bool {
+    fn eval(&self, rec: &HitRecord, _wo: Vec3, wi: Vec3) -> Color {
+        if wi.dot(rec.normal) > 0. {
+            self.albedo / PI
+        } else {
+            Color::new()
+        }
+    }
+
+    fn sample(&self, r_in: &Ray, rec: &HitRecord) -> Option<BsdfSample> {
         let mut scatter_direction = rec.normal + Vec3::random_unit_vector();
         // Catch degenerate scatter direction
         if scatter_direction.near_zero() {
             scatter_direction = rec.normal;
         }
-        *scattered = Ray::new(rec.p, scatter_direction);
-        *attenuation = self.albedo;
-        return true;
+        let wo = -r_in.direction().unit_vector();
+        let wi = scatter_direction.unit_vector();
+        let pdf = self.pdf(rec, wo, wi);
+        (pdf > 0.).then(|| BsdfSample {
+            scattered: r_in.spawn(rec.p, wi),
+            f: self.albedo / PI,
+            pdf,
+            is_delta: false,
+            is_spectral: false,
+        })
+    }
+
+    fn pdf(&self, rec: &HitRecord, _wo: Vec3, wi: Vec3) -> f64 {
+        // The sampled directions follow a cosine distribution around the normal.
+        wi.dot(rec.normal).max(0.) / PI
+    }
+}
+
+/// A rough diffuse surface, made of tiny V-shaped facets that each reflect light like a
+/// Lambertian surface. It looks flatter than a Lambertian surface and reflects more light back
+/// toward the light source, like clay or the moon.
+pub struct OrenNayar {
+    albedo: Color,
+    a: f64,
+    b: f64,
+}
+
+impl OrenNayar {
+    /// A surface whose facets have slopes with a standard deviation of `sigma` degrees. A
+    /// `sigma` of 0 gives a Lambertian surface.
+    pub fn new(albedo: Color, sigma: f64) -> Self {
+        let sigma2 = sigma.to_radians().powi(2);
+        Self {
+            albedo,
+            a: 1. - sigma2 / (2. * (sigma2 + 0.33)),
+            b: 0.45 * sigma2 / (sigma2 + 0.09),
+        }
+    }
+}
+
+impl Material for OrenNayar {
+    fn eval(&self, rec: &HitRecord, wo: Vec3, wi: Vec3) -> Color {
+        let cos_theta_i = wi.dot(rec.normal);
+        let cos_theta_o = wo.dot(rec.normal);
+        if cos_theta_i <= 0. || cos_theta_o <= 0. {
+            return Color::new();
+        }
+        let sin_theta_i = (1. - cos_theta_i * cos_theta_i).max(0.).sqrt();
+        let sin_theta_o = (1. - cos_theta_o * cos_theta_o).max(0.).sqrt();
+
+        // Cosine of the azimuthal angle between both directions.
+        let tangent_i = wi - cos_theta_i * rec.normal;
+        let tangent_o = wo - cos_theta_o * rec.normal;
+        let lengths = tangent_i.length() * tangent_o.length();
+        let cos_phi = if lengths > 1e-8 {
+            (tangent_i.dot(tangent_o) / lengths).max(0.)
+        } else {
+            0.
+        };
+
+        // Sine of the larger angle to the normal, tangent of the smaller one.
+        let (sin_alpha, tan_beta) = if cos_theta_i > cos_theta_o {
+            (sin_theta_o, sin_theta_i / cos_theta_i)
+        } else {
+            (sin_theta_i, sin_theta_o / cos_theta_o)
+        };
+        self.albedo / PI * (self.a + self.b * cos_phi * sin_alpha * tan_beta)
+    }
+
+    fn sample(&self, r_in: &Ray, rec: &HitRecord) -> Option<BsdfSample> {
+        let wo = -r_in.direction().unit_vector();
+        let wi = Onb::new(rec.normal).transform(Vec3::random_cosine_direction());
+        let pdf = self.pdf(rec, wo, wi);
+        (pdf > 0.).then(|| BsdfSample {
+            scattered: r_in.spawn(rec.p, wi),
+            f: self.eval(rec, wo, wi),
+            pdf,
+            is_delta: false,
+            is_spectral: false,
+        })
+    }
+
+    fn pdf(&self, rec: &HitRecord, _wo: Vec3, wi: Vec3) -> f64 {
+        wi.dot(rec.normal).max(0.) / PI
+    }
+}
+
+/// A soft glow at grazing angles from fibers sticking out of the surface, like on velvet or
+/// other cloth. It only reflects a little light, so it's meant to be mixed with a diffuse
+/// material.
+pub struct Sheen {
+    color: Color,
+    roughness: f64,
+}
+
+impl Sheen {
+    /// A sheen whose `roughness` between 0 and 1 spreads the glow away from grazing angles.
+    pub fn new(color: Color, roughness: f64) -> Self {
+        Self {
+            color,
+            roughness: roughness.clamp(0.01, 1.),
+        }
+    }
+
+    /// The "Charlie" distribution of fiber normals, after Estevez and Kulla.
+    fn distribution(&self, cos_theta_h: f64) -> f64 {
+        let inv_alpha = 1. / self.roughness;
+        let sin_theta_h = (1. - cos_theta_h * cos_theta_h).max(0.).sqrt();
+        (2. + inv_alpha) * sin_theta_h.powf(inv_alpha) / (2. * PI)
+    }
+}
+
+impl Material for Sheen {
+    fn eval(&self, rec: &HitRecord, wo: Vec3, wi: Vec3) -> Color {
+        let cos_theta_i = wi.dot(rec.normal);
+        let cos_theta_o = wo.dot(rec.normal);
+        if cos_theta_i <= 0. || cos_theta_o <= 0. {
+            return Color::new();
+        }
+        let cos_theta_h = (wi + wo).unit_vector().dot(rec.normal);
+        // Ashikhmin's visibility term, smooth and cheap compared to the exact masking of fibers.
+        let visibility = 1. / (4. * (cos_theta_i + cos_theta_o - cos_theta_i * cos_theta_o));
+        self.color * self.distribution(cos_theta_h) * visibility
+    }
+
+    fn sample(&self, r_in: &Ray, rec: &HitRecord) -> Option<BsdfSample> {
+        // The lobe is wide enough to be sampled like a diffuse surface.
+        let wo = -r_in.direction().unit_vector();
+        let wi = Onb::new(rec.normal).transform(Vec3::random_cosine_direction());
+        let pdf = self.pdf(rec, wo, wi);
+        (pdf > 0.).then(|| BsdfSample {
+            scattered: r_in.spawn(rec.p, wi),
+            f: self.eval(rec, wo, wi),
+            pdf,
+            is_delta: false,
+            is_spectral: false,
+        })
+    }
+
+    fn pdf(&self, rec: &HitRecord, _wo: Vec3, wi: Vec3) -> f64 {
+        wi.dot(rec.normal).max(0.) / PI
+    }
+}
+
+/// A thin translucent sheet, like a leaf or a paper lampshade, scattering light diffusely on
+/// both of its sides.
+pub struct LambertianTransmission {
+    reflectance: Color,
+    transmittance: Color,
+}
+
+impl LambertianTransmission {
+    /// A sheet reflecting `reflectance` of the light back and letting `transmittance` of it
+    /// through. Their sum should not exceed 1.
+    pub fn new(reflectance: Color, transmittance: Color) -> Self {
+        Self {
+            reflectance,
+            transmittance,
+        }
+    }
+
+    /// Probability of sampling a reflected direction rather than a transmitted one.
+    fn reflection_probability(&self) -> f64 {
+        let r = self.reflectance.r() + self.reflectance.g() + self.reflectance.b();
+        let t = self.transmittance.r() + self.transmittance.g() + self.transmittance.b();
+        if r + t > 0. {
+            r / (r + t)
+        } else {
+            0.5
+        }
+    }
+}
+
+impl Material for LambertianTransmission {
+    fn eval(&self, rec: &HitRecord, wo: Vec3, wi: Vec3) -> Color {
+        if wi.dot(rec.normal) * wo.dot(rec.normal) > 0. {
+            self.reflectance / PI
+        } else {
+            self.transmittance / PI
+        }
+    }
+
+    fn sample(&self, r_in: &Ray, rec: &HitRecord) -> Option<BsdfSample> {
+        let wo = -r_in.direction().unit_vector();
+        let normal = if wo.dot(rec.normal) > 0. {
+            rec.normal
+        } else {
+            -rec.normal
+        };
+        let direction = Onb::new(normal).transform(Vec3::random_cosine_direction());
+        let wi = if crate::random_float() < self.reflection_probability() {
+            direction
+        } else {
+            -direction
+        };
+        let pdf = self.pdf(rec, wo, wi);
+        (pdf > 0.).then(|| BsdfSample {
+            scattered: r_in.spawn(rec.p, wi),
+            f: self.eval(rec, wo, wi),
+            pdf,
+            is_delta: false,
+            is_spectral: false,
+        })
+    }
+
+    fn pdf(&self, rec: &HitRecord, wo: Vec3, wi: Vec3) -> f64 {
+        let cos_theta = wi.dot(rec.normal).abs() / PI;
+        if wi.dot(rec.normal) * wo.dot(rec.normal) > 0. {
+            self.reflection_probability() * cos_theta
+        } else {
+            (1. - self.reflection_probability()) * cos_theta
+        }
     }
 }
 
 pub struct Metal {
     albedo: Color,
     fuzz: f64,
+    film: Option<ThinFilm>,
 }
 
 impl Metal {
@@ -51,34 +341,314 @@ impl Metal {
         Self {
             albedo,
             fuzz: fuzz.clamp(0., 1.),
+            film: None,
+        }
+    }
+
+    /// This metal under a thin film, like anodized or heat-tinted metal. The albedo of the
+    /// metal gives its index of refraction under the film.
+    pub fn with_thin_film(self, film: ThinFilm) -> Self {
+        Self {
+            film: Some(film),
+            ..self
         }
     }
 }
 
 impl Material for Metal {
-    fn scatter(
-        &self,
-        r_in: &Ray,
-        rec: &HitRecord,
-        attenuation: &mut Color,
-        scattered: &mut Ray,
-    ) -> bool {
-        let reflected = r_in.direction().unit_vector().reflect_with(rec.normal);
-        *scattered = Ray::new(rec.p, reflected + self.fuzz * Vec3::random_unit_vector());
-        *attenuation = self.albedo;
+    fn eval(&self, _rec: &HitRecord, _wo: Vec3, _wi: Vec3) -> Color {
+        Color::new()
+    }
+
+    fn sample(&self, r_in: &Ray, rec: &HitRecord) -> Option<BsdfSample> {
+        // The fuzzed reflection has no closed-form density, so it's treated like a delta lobe.
+        let unit_direction = r_in.direction().unit_vector();
+        let reflected = unit_direction.reflect_with(rec.normal);
+        let direction = reflected + self.fuzz * Vec3::random_unit_vector();
+
+        let f = match &self.film {
+            Some(film) => {
+                let cos_theta = (-unit_direction).dot(rec.normal);
+                let eta_i = r_in
+                    .media()
+                    .current()
+                    .map_or(1., |medium| medium.ior.at(r_in.wavelength()));
+                film.reflectance(r_in, rec, cos_theta, eta_i, |wavelength| {
+                    let albedo = spectrum::rgb_to_spectrum(self.albedo, wavelength);
+                    fresnel::gulbrandsen(albedo, albedo)
+                })
+            }
+            None => self.albedo,
+        };
+
+        (direction.dot(rec.normal) > 0.).then(|| BsdfSample {
+            scattered: r_in.spawn(rec.p, direction),
+            f,
+            pdf: 1.,
+            is_delta: true,
+            is_spectral: self.film.is_some() && r_in.hero_wavelengths().is_some(),
+        })
+    }
+
+    fn pdf(&self, _rec: &HitRecord, _wo: Vec3, _wi: Vec3) -> f64 {
+        0.
+    }
+
+    fn is_delta(&self) -> bool {
+        true
+    }
+}
+
+/// A metal with a physically based rough surface, made of GGX microfacets reflecting light
+/// according to the complex index of refraction of the metal.
+pub struct Conductor {
+    eta: Color,                    // Real part of the index of refraction, per channel
+    k: Color,                      // Absorption coefficient, per channel
+    distribution: TrowbridgeReitz, // Roughness of the surface
+}
+
+impl Conductor {
+    pub fn new(eta: Color, k: Color, roughness: f64) -> Self {
+        Self::anisotropic(eta, k, roughness, roughness)
+    }
+
+    /// A conductor whose roughness differs along the two tangent directions of the surface,
+    /// stretching the highlights like on brushed metal.
+    pub fn anisotropic(eta: Color, k: Color, roughness_u: f64, roughness_v: f64) -> Self {
+        Self {
+            eta,
+            k,
+            distribution: TrowbridgeReitz::from_roughness(roughness_u, roughness_v),
+        }
+    }
 
-        scattered.direction().dot(rec.normal) > 0.
+    pub fn gold(roughness: f64) -> Self {
+        Self::new(
+            Color::from(0.143, 0.374, 1.442),
+            Color::from(3.983, 2.385, 1.603),
+            roughness,
+        )
+    }
+
+    pub fn copper(roughness: f64) -> Self {
+        Self::new(
+            Color::from(0.200, 0.924, 1.102),
+            Color::from(3.912, 2.452, 2.142),
+            roughness,
+        )
+    }
+
+    pub fn aluminum(roughness: f64) -> Self {
+        Self::new(
+            Color::from(1.657, 0.880, 0.521),
+            Color::from(9.224, 6.270, 4.837),
+            roughness,
+        )
+    }
+
+    pub fn silver(roughness: f64) -> Self {
+        Self::new(
+            Color::from(0.155, 0.117, 0.138),
+            Color::from(4.828, 3.122, 2.147),
+            roughness,
+        )
+    }
+
+    fn fresnel(&self, cos_theta: f64) -> Color {
+        let channel =
+            |n: usize| fresnel::conductor(cos_theta, Complex::new(self.eta[n], self.k[n]));
+        Color::from(channel(0), channel(1), channel(2))
+    }
+}
+
+impl Material for Conductor {
+    fn eval(&self, rec: &HitRecord, wo: Vec3, wi: Vec3) -> Color {
+        if self.distribution.effectively_smooth() {
+            return Color::new();
+        }
+        let uvw = rec.shading_frame();
+        let (wo, wi) = (uvw.to_local(wo), uvw.to_local(wi));
+        if wo.z() <= 0. || wi.z() <= 0. {
+            return Color::new();
+        }
+        let wm = wo + wi;
+        if wm.near_zero() {
+            return Color::new();
+        }
+        let wm = wm.unit_vector();
+
+        let d = self.distribution.d(wm);
+        let g = self.distribution.g(wo, wi);
+        self.fresnel(wo.dot(wm).abs()) * (d * g / (4. * wo.z() * wi.z()))
+    }
+
+    fn sample(&self, r_in: &Ray, rec: &HitRecord) -> Option<BsdfSample> {
+        let wo = -r_in.direction().unit_vector();
+        if self.distribution.effectively_smooth() {
+            let wi = (-wo).reflect_with(rec.normal);
+            return Some(BsdfSample {
+                scattered: r_in.spawn(rec.p, wi),
+                f: self.fresnel(wo.dot(rec.normal)),
+                pdf: 1.,
+                is_delta: true,
+                is_spectral: false,
+            });
+        }
+
+        // Reflect about the normal of a microfacet visible from `wo`.
+        let uvw = rec.shading_frame();
+        let wo_local = uvw.to_local(wo);
+        if wo_local.z() <= 0. {
+            return None;
+        }
+        let wm = self.distribution.sample_wm(wo_local);
+        let wi_local = (-wo_local).reflect_with(wm);
+        if wi_local.z() <= 0. {
+            return None;
+        }
+
+        let wi = uvw.transform(wi_local);
+        let pdf = self.pdf(rec, wo, wi);
+        (pdf > 0.).then(|| BsdfSample {
+            scattered: r_in.spawn(rec.p, wi),
+            f: self.eval(rec, wo, wi),
+            pdf,
+            is_delta: false,
+            is_spectral: false,
+        })
+    }
+
+    fn pdf(&self, rec: &HitRecord, wo: Vec3, wi: Vec3) -> f64 {
+        if self.distribution.effectively_smooth() {
+            return 0.;
+        }
+        let uvw = rec.shading_frame();
+        let (wo, wi) = (uvw.to_local(wo), uvw.to_local(wi));
+        if wo.z() <= 0. || wi.z() <= 0. {
+            return 0.;
+        }
+        let wm = wo + wi;
+        if wm.near_zero() {
+            return 0.;
+        }
+        let wm = wm.unit_vector();
+        // Change of variables from the microfacet normal to the reflected direction.
+        self.distribution.d_visible(wo, wm) / (4. * wo.dot(wm).abs())
+    }
+
+    fn is_delta(&self) -> bool {
+        self.distribution.effectively_smooth()
+    }
+}
+
+/// An index of refraction, possibly varying with the wavelength of the light to disperse it.
+#[derive(Copy, Clone, Debug)]
+pub enum Ior {
+    Constant(f64),
+    /// Cauchy's equation `a + b / λ²`, with λ in micrometers.
+    Cauchy {
+        a: f64,
+        b: f64,
+    },
+    /// Sellmeier's equation `n² = 1 + Σ b λ² / (λ² - c)`, with λ in micrometers.
+    Sellmeier {
+        b: [f64; 3],
+        c: [f64; 3],
+    },
+}
+
+impl Ior {
+    /// Schott N-BK7, the most common optical glass.
+    pub const BK7: Ior = Ior::Sellmeier {
+        b: [1.03961212, 0.231792344, 1.01046945],
+        c: [0.00600069867, 0.0200179144, 103.560653],
+    };
+    /// Diamond, with its strong dispersion.
+    pub const DIAMOND: Ior = Ior::Sellmeier {
+        b: [4.3356, 0.3306, 0.],
+        c: [0.01124, 0.030625, 0.],
+    };
+
+    /// The index of refraction at `wavelength`, in nanometers. Light of every wavelength uses
+    /// the index at the yellow helium line, the one usually given for a material.
+    pub fn at(&self, wavelength: Option<f64>) -> f64 {
+        let lambda = wavelength.unwrap_or(587.6) / 1000.;
+        let lambda2 = lambda * lambda;
+        match *self {
+            Ior::Constant(ior) => ior,
+            Ior::Cauchy { a, b } => a + b / lambda2,
+            Ior::Sellmeier { b, c } => {
+                let mut n2 = 1.;
+                for (b, c) in b.into_iter().zip(c) {
+                    n2 += b * lambda2 / (lambda2 - c);
+                }
+                n2.sqrt()
+            }
+        }
+    }
+
+    /// Whether the index changes with the wavelength.
+    pub fn is_dispersive(&self) -> bool {
+        !matches!(self, Ior::Constant(_))
     }
 }
 
 pub struct Dielectric {
-    ir: f64,
+    medium: Medium,
+    film: Option<ThinFilm>,
+    id: usize, // Identifies the medium of this dielectric in the medium stack of the rays
 }
 
+/// The next identifier to give to the inside of a material, unique for the whole program.
+static NEXT_MEDIUM_ID: AtomicUsize = AtomicUsize::new(0);
+
 impl Dielectric {
     pub fn new(index_of_refraction: f64) -> Self {
+        Self::with_ior(Ior::Constant(index_of_refraction))
+    }
+
+    /// A dielectric whose index of refraction may vary with the wavelength. Paths crossing a
+    /// dispersive dielectric carry a single wavelength from then on.
+    pub fn with_ior(ior: Ior) -> Self {
+        Self::with_medium(Medium::new(ior))
+    }
+
+    /// A tinted dielectric, absorbing light as it travels inside following the Beer-Lambert
+    /// law: the light left after a distance `d` is `exp(-absorption * d)`.
+    pub fn with_absorption(ior: Ior, absorption: Color) -> Self {
+        Self::with_medium(Medium {
+            absorption,
+            ..Medium::new(ior)
+        })
+    }
+
+    /// A translucent material, like skin, wax or marble: light enters the object and bounces
+    /// around inside before coming out. It looks like `color`, and `radius` is how far light
+    /// travels between bounces on average, per channel.
+    pub fn subsurface(index_of_refraction: f64, color: Color, radius: Color) -> Self {
+        Self::with_medium(Medium::subsurface(
+            Ior::Constant(index_of_refraction),
+            color,
+            radius,
+        ))
+    }
+
+    /// A dielectric filled with `medium`, whose priority decides which object fills the space
+    /// where it overlaps others.
+    pub fn with_medium(medium: Medium) -> Self {
+        Self {
+            medium,
+            film: None,
+            id: NEXT_MEDIUM_ID.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    /// This dielectric under a thin film, like an oil slick on water. A dielectric with an
+    /// index of refraction of 1 under a film makes a soap bubble.
+    pub fn with_thin_film(self, film: ThinFilm) -> Self {
         Self {
-            ir: index_of_refraction,
+            film: Some(film),
+            ..self
         }
     }
 
@@ -88,39 +658,1099 @@ impl Dielectric {
         r0 = r0 * r0;
         r0 + (1. - r0) * (1. - cosine).powi(5)
     }
+
+    /// The media on both sides of the surface for a ray traveling through `media`: outside of
+    /// this object, then inside of it.
+    fn sides(&self, media: &MediumStack) -> (MediumStack, MediumStack) {
+        let mut outside = media.clone();
+        outside.remove(self.id);
+        let mut inside = outside.clone();
+        inside.push(self.id, self.medium);
+        (outside, inside)
+    }
+
+    /// Whether a medium with a higher priority fills the space `outside` of this object, in
+    /// which case the surface doesn't exist.
+    fn is_hidden(&self, outside: &MediumStack) -> bool {
+        outside
+            .current()
+            .is_some_and(|current| current.priority > self.medium.priority)
+    }
 }
 
 impl Material for Dielectric {
-    fn scatter(
+    fn eval(&self, _rec: &HitRecord, _wo: Vec3, _wi: Vec3) -> Color {
+        Color::new()
+    }
+
+    fn sample(&self, r_in: &Ray, rec: &HitRecord) -> Option<BsdfSample> {
+        let entering = rec.front_face;
+        let unit_direction = r_in.direction().unit_vector();
+
+        let (outside, inside) = self.sides(r_in.media());
+        if self.is_hidden(&outside) {
+            let media = if entering { inside } else { outside };
+            return Some(BsdfSample {
+                scattered: r_in.spawn(rec.p, unit_direction).with_media(media),
+                f: Color::from(1, 1, 1),
+                pdf: 1.,
+                is_delta: true,
+                is_spectral: false,
+            });
+        }
+
+        // A dispersive dielectric splits the light, so pick the wavelength this path follows.
+        let mut attenuation = Color::from(1.0, 1.0, 1.0);
+        let mut sampled_wavelength = None;
+        let dispersive = self.medium.ior.is_dispersive()
+            || outside
+                .current()
+                .is_some_and(|medium| medium.ior.is_dispersive());
+        if r_in.wavelength().is_none() && dispersive {
+            let wavelength = spectrum::sample_wavelength();
+            attenuation = spectrum::wavelength_weight(wavelength);
+            sampled_wavelength = Some(wavelength);
+        }
+        let wavelength = r_in.wavelength().or(sampled_wavelength);
+        let ir = self.medium.ior.at(wavelength);
+        let outside_ir = outside
+            .current()
+            .map_or(1., |medium| medium.ior.at(wavelength));
+
+        let refraction_ratio = if entering {
+            outside_ir / ir
+        } else {
+            ir / outside_ir
+        };
+
+        let cos_theta = (-unit_direction).dot(rec.normal).min(1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+
+        let cannot_refract = refraction_ratio * sin_theta > 1.0;
+
+        let reflect = match &self.film {
+            Some(film) if !cannot_refract => {
+                // The film reflects each wavelength differently, so pick the reflection
+                // according to the average reflectance and weight the color accordingly.
+                let (eta_i, eta_t) = if entering {
+                    (outside_ir, ir)
+                } else {
+                    (ir, outside_ir)
+                };
+                let reflectance =
+                    film.reflectance(r_in, rec, cos_theta, eta_i, |_| Complex::from(eta_t));
+                let probability = (reflectance.r() + reflectance.g() + reflectance.b()) / 3.;
+                let reflect = probability > crate::random_float();
+                attenuation *= if reflect {
+                    reflectance / probability
+                } else {
+                    (Color::from(1, 1, 1) - reflectance) / (1. - probability)
+                };
+                reflect
+            }
+            _ => {
+                cannot_refract
+                    || Self::reflectance(cos_theta, refraction_ratio) > crate::random_float()
+            }
+        };
+
+        let mut scattered = if reflect {
+            r_in.spawn(rec.p, unit_direction.reflect_with(rec.normal))
+        } else {
+            let direction = unit_direction.refract_with(rec.normal, refraction_ratio);
+            let media = if entering { inside } else { outside };
+            r_in.spawn(rec.p, direction).with_media(media)
+        };
+
+        if let Some(wavelength) = sampled_wavelength {
+            scattered = scattered.with_wavelength(wavelength);
+        }
+        // A spectral path can only follow its hero wavelength through dispersion.
+        if let Some(hero) = r_in.hero_wavelengths() {
+            if dispersive {
+                scattered = scattered.with_hero_wavelengths(hero.terminate_secondary());
+            }
+        }
+        Some(BsdfSample {
+            scattered,
+            f: attenuation,
+            pdf: 1.,
+            is_delta: true,
+            is_spectral: self.film.is_some() && r_in.hero_wavelengths().is_some(),
+        })
+    }
+
+    fn pdf(&self, _rec: &HitRecord, _wo: Vec3, _wi: Vec3) -> f64 {
+        0.
+    }
+
+    fn is_delta(&self) -> bool {
+        true
+    }
+}
+
+/// A film a few hundred nanometers thick on top of a surface. Light reflected on both sides of
+/// the film interferes, reflecting some wavelengths more than others and giving soap bubbles
+/// and oil slicks their iridescent colors.
+pub struct ThinFilm {
+    /// Index of refraction of the film.
+    pub ior: f64,
+    /// Thickness of the film in nanometers where the texture is black.
+    pub min_thickness: f64,
+    /// Thickness of the film in nanometers where the texture is white.
+    pub max_thickness: f64,
+    /// Where the film is thicker, according to its red channel.
+    pub thickness: Arc<dyn Texture>,
+}
+
+impl ThinFilm {
+    /// A film of uniform `thickness` in nanometers.
+    pub fn new(ior: f64, thickness: f64) -> Self {
+        Self::with_texture(ior, thickness, thickness, Arc::new(SolidColor::gray(0.)))
+    }
+
+    /// A film whose thickness varies over the surface between `min_thickness` and
+    /// `max_thickness` nanometers, following `thickness`.
+    pub fn with_texture(
+        ior: f64,
+        min_thickness: f64,
+        max_thickness: f64,
+        thickness: Arc<dyn Texture>,
+    ) -> Self {
+        Self {
+            ior,
+            min_thickness,
+            max_thickness,
+            thickness,
+        }
+    }
+
+    /// Reflectance of the film on a substrate whose index of refraction at each wavelength is
+    /// given by `eta_t`, for light coming from a medium of index `eta_i`. It's spectral for a
+    /// spectral `r_in`, and RGB otherwise.
+    fn reflectance(
         &self,
         r_in: &Ray,
         rec: &HitRecord,
-        attenuation: &mut Color,
-        scattered: &mut Ray,
-    ) -> bool {
-        *attenuation = Color::from(1.0, 1.0, 1.0);
-        let refraction_ratio = if rec.front_face {
-            1.0 / self.ir
+        cos_theta: f64,
+        eta_i: f64,
+        eta_t: impl Fn(f64) -> Complex,
+    ) -> Color {
+        let t = self.thickness.value(rec.u, rec.v, rec.p).r().clamp(0., 1.);
+        let thickness = self.min_thickness + t * (self.max_thickness - self.min_thickness);
+        let reflectance = |wavelength| {
+            fresnel::thin_film(
+                cos_theta,
+                eta_i,
+                self.ior,
+                eta_t(wavelength),
+                thickness,
+                wavelength,
+            )
+        };
+
+        // A path following a single wavelength sees the reflectance at that wavelength only,
+        // and a spectral path at each of its wavelengths. Other colors are RGB and average the
+        // reflectance over each channel.
+        match (r_in.wavelength(), r_in.hero_wavelengths()) {
+            (_, Some(hero)) => {
+                let [a, b, c] = hero.wavelengths().map(reflectance);
+                Color::from(a, b, c)
+            }
+            (Some(wavelength), None) => {
+                let r = reflectance(wavelength);
+                Color::from(r, r, r)
+            }
+            (None, None) => spectrum::spectrum_to_rgb(reflectance).map(|r| r.clamp(0., 1.)),
+        }
+    }
+}
+
+/// Frosted glass, made of GGX microfacets that both reflect and refract light.
+pub struct RoughDielectric {
+    dielectric: Dielectric, // The same surface when smooth, whose medium fills the object
+    distribution: TrowbridgeReitz, // Roughness of the surface
+}
+
+impl RoughDielectric {
+    pub fn new(index_of_refraction: f64, roughness: f64) -> Self {
+        Self::with_medium(Medium::new(Ior::Constant(index_of_refraction)), roughness)
+    }
+
+    /// A rough dielectric filled with `medium`, like [`Dielectric::with_medium`]. The index of
+    /// refraction doesn't vary with the wavelength on a rough surface.
+    pub fn with_medium(medium: Medium, roughness: f64) -> Self {
+        Self {
+            dielectric: Dielectric::with_medium(medium),
+            distribution: TrowbridgeReitz::from_roughness(roughness, roughness),
+        }
+    }
+
+    /// Index of refraction of the inside relative to the outside for a ray traveling through
+    /// `media`, or `None` if light goes straight through like on a smooth surface: when the
+    /// surface has no roughness, a medium with a higher priority hides it, or the media on
+    /// both sides have the same index of refraction.
+    fn eta(&self, media: &MediumStack) -> Option<f64> {
+        let (outside, _) = self.dielectric.sides(media);
+        if self.distribution.effectively_smooth() || self.dielectric.is_hidden(&outside) {
+            return None;
+        }
+        let outside_ir = outside.current().map_or(1., |medium| medium.ior.at(None));
+        let eta = self.dielectric.medium.ior.at(None) / outside_ir;
+        ((eta - 1.).abs() > 1e-9).then_some(eta)
+    }
+
+    /// A frame whose z axis is the normal pointing out of the dielectric, whichever side the
+    /// ray hit.
+    fn frame(rec: &HitRecord) -> Onb {
+        Onb::new(if rec.front_face {
+            rec.normal
+        } else {
+            -rec.normal
+        })
+    }
+
+    /// The microfacet normal that scatters `wo` into `wi`, facing out of the dielectric, or
+    /// `None` if no microfacet visible from both directions can.
+    fn half_vector(eta: f64, wo: Vec3, wi: Vec3) -> Option<Vec3> {
+        let (cos_theta_o, cos_theta_i) = (wo.z(), wi.z());
+        if cos_theta_o == 0. || cos_theta_i == 0. {
+            return None;
+        }
+        // For a refraction, the half vector is weighted by the index of refraction of the
+        // side `wi` is on.
+        let etap = if cos_theta_o * cos_theta_i > 0. {
+            1.
+        } else if cos_theta_o > 0. {
+            eta
         } else {
-            self.ir
+            1. / eta
         };
+        let wm = wi * etap + wo;
+        if wm.near_zero() {
+            return None;
+        }
+        let wm = wm.unit_vector();
+        let wm = if wm.z() < 0. { -wm } else { wm };
 
-        let unit_direction = r_in.direction().unit_vector();
+        // Discard microfacets that face away from either direction.
+        (wm.dot(wi) * cos_theta_i > 0. && wm.dot(wo) * cos_theta_o > 0.).then_some(wm)
+    }
 
-        let cos_theta = (-unit_direction).dot(rec.normal).min(1.0);
-        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+    /// Value of the BSDF for a relative index of refraction `eta`, with directions in the
+    /// frame of the surface.
+    fn eval_local(&self, eta: f64, wo: Vec3, wi: Vec3) -> f64 {
+        let Some(wm) = Self::half_vector(eta, wo, wi) else {
+            return 0.;
+        };
 
-        let cannot_refract = refraction_ratio * sin_theta > 1.0;
-        let direction;
+        let d = self.distribution.d(wm);
+        let g = self.distribution.g(wo, wi);
+        let f = fresnel::dielectric(wo.dot(wm), eta);
+        if wo.z() * wi.z() > 0. {
+            d * g * f / (4. * wo.z() * wi.z()).abs()
+        } else {
+            // Like `Dielectric`, the radiance isn't scaled by the squared ratio of the indices
+            // of refraction when crossing the surface.
+            let etap = if wo.z() > 0. { eta } else { 1. / eta };
+            let denom = wi.dot(wm) + wo.dot(wm) / etap;
+            d * g * (1. - f) * (wi.dot(wm) * wo.dot(wm) / (denom * denom * wi.z() * wo.z())).abs()
+        }
+    }
+
+    /// Probability density of sampling `wi` for a relative index of refraction `eta`, with
+    /// directions in the frame of the surface.
+    fn pdf_local(&self, eta: f64, wo: Vec3, wi: Vec3) -> f64 {
+        let Some(wm) = Self::half_vector(eta, wo, wi) else {
+            return 0.;
+        };
+
+        let reflectance = fresnel::dielectric(wo.dot(wm), eta);
+        let d_visible = self.distribution.d_visible(wo, wm);
+        // Change of variables from the microfacet normal to the scattered direction.
+        if wo.z() * wi.z() > 0. {
+            d_visible / (4. * wo.dot(wm).abs()) * reflectance
+        } else {
+            let etap = if wo.z() > 0. { eta } else { 1. / eta };
+            let denom = wi.dot(wm) + wo.dot(wm) / etap;
+            d_visible * wi.dot(wm).abs() / (denom * denom) * (1. - reflectance)
+        }
+    }
+}
+
+impl Material for RoughDielectric {
+    fn eval(&self, rec: &HitRecord, wo: Vec3, wi: Vec3) -> Color {
+        let Some(eta) = self.eta(&rec.media) else {
+            return Color::new();
+        };
+        let uvw = Self::frame(rec);
+        let value = self.eval_local(eta, uvw.to_local(wo), uvw.to_local(wi));
+        Color::from(value, value, value)
+    }
+
+    fn sample(&self, r_in: &Ray, rec: &HitRecord) -> Option<BsdfSample> {
+        let Some(eta) = self.eta(r_in.media()) else {
+            return self.dielectric.sample(r_in, rec);
+        };
+
+        // Pick a visible microfacet, then reflect or refract on it depending on its Fresnel
+        // reflectance.
+        let uvw = Self::frame(rec);
+        let wo = -r_in.direction().unit_vector();
+        let wo_local = uvw.to_local(wo);
+        if wo_local.z() == 0. {
+            return None;
+        }
+        let wm = self.distribution.sample_wm(wo_local);
+        let reflectance = fresnel::dielectric(wo_local.dot(wm), eta);
+        let (wi_local, reflected) = if crate::random_float() < reflectance {
+            ((-wo_local).reflect_with(wm), true)
+        } else {
+            (refract(wo_local, wm, eta)?, false)
+        };
+        // Directions scattered by a microfacet to the wrong side of the surface are lost.
+        if (wo_local.z() * wi_local.z() > 0.) != reflected {
+            return None;
+        }
+
+        let pdf = self.pdf_local(eta, wo_local, wi_local);
+        let value = self.eval_local(eta, wo_local, wi_local);
+        let mut scattered = r_in.spawn(rec.p, uvw.transform(wi_local));
+        if !reflected {
+            let (outside, inside) = self.dielectric.sides(r_in.media());
+            scattered = scattered.with_media(if wi_local.z() < 0. { inside } else { outside });
+        }
+        (pdf > 0.).then(|| BsdfSample {
+            scattered,
+            f: Color::from(value, value, value),
+            pdf,
+            is_delta: false,
+            is_spectral: false,
+        })
+    }
+
+    fn pdf(&self, rec: &HitRecord, wo: Vec3, wi: Vec3) -> f64 {
+        let Some(eta) = self.eta(&rec.media) else {
+            return 0.;
+        };
+        let uvw = Self::frame(rec);
+        self.pdf_local(eta, uvw.to_local(wo), uvw.to_local(wi))
+    }
+
+    fn media_toward(&self, r_in: &Ray, rec: &HitRecord, wi: Vec3) -> MediumStack {
+        let (outside, inside) = self.dielectric.sides(r_in.media());
+        let outward = if rec.front_face {
+            rec.geometric_normal
+        } else {
+            -rec.geometric_normal
+        };
+        if wi.dot(outward) < 0. {
+            inside
+        } else {
+            outside
+        }
+    }
+
+    fn is_delta(&self) -> bool {
+        self.distribution.effectively_smooth()
+    }
+}
+
+/// Refract `w`, pointing away from the surface, through the interface of normal `n` into a
+/// medium of relative index of refraction `eta`, or return `None` on total internal reflection.
+/// The normal and index are flipped when `w` comes from the other side.
+fn refract(w: Vec3, n: Vec3, eta: f64) -> Option<Vec3> {
+    let (n, eta, cos_theta_i) = if w.dot(n) < 0. {
+        (-n, 1. / eta, -w.dot(n))
+    } else {
+        (n, eta, w.dot(n))
+    };
+    let sin2_theta_t = (1. - cos_theta_i * cos_theta_i).max(0.) / (eta * eta);
+    if sin2_theta_t >= 1. {
+        return None;
+    }
+    let cos_theta_t = (1. - sin2_theta_t).sqrt();
+    Some(-w / eta + (cos_theta_i / eta - cos_theta_t) * n)
+}
+
+/// A surface emitting light evenly from its front face, and absorbing everything it receives.
+pub struct DiffuseLight {
+    emit: Color,
+    spectrum: Option<(Spectrum, f64)>, // Emission spectrum and its scale, for spectral rays
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> Self {
+        Self {
+            emit,
+            spectrum: None,
+        }
+    }
+
+    /// A light emitting `scale` times `spectrum`. RGB rays get the color of the spectrum.
+    pub fn with_spectrum(spectrum: Spectrum, scale: f64) -> Self {
+        Self {
+            emit: scale * spectrum.to_rgb(),
+            spectrum: Some((spectrum, scale)),
+        }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn eval(&self, _rec: &HitRecord, _wo: Vec3, _wi: Vec3) -> Color {
+        Color::new()
+    }
+
+    fn sample(&self, _r_in: &Ray, _rec: &HitRecord) -> Option<BsdfSample> {
+        None
+    }
+
+    fn pdf(&self, _rec: &HitRecord, _wo: Vec3, _wi: Vec3) -> f64 {
+        0.
+    }
+
+    fn emitted(&self, r_in: &Ray, rec: &HitRecord) -> Color {
+        if !rec.front_face {
+            return Color::new();
+        }
+        match (&self.spectrum, r_in.hero_wavelengths()) {
+            (Some((spectrum, scale)), Some(hero)) => {
+                let [a, b, c] = hero
+                    .wavelengths()
+                    .map(|wavelength| spectrum.value(wavelength));
+                *scale * Color::from(a, b, c)
+            }
+            _ => r_in.spectrum(self.emit),
+        }
+    }
+
+    fn is_emissive(&self) -> bool {
+        true
+    }
+}
 
-        if cannot_refract || Self::reflectance(cos_theta, refraction_ratio) > crate::random_float()
-        {
-            direction = unit_direction.reflect_with(rec.normal);
+/// An artist friendly material layering a diffuse base, a metallic or dielectric specular
+/// highlight, sheen, a clear coat and glass-like transmission, after Disney's principled BSDF.
+///
+/// Every parameter can vary over the surface. Scalar parameters, between 0 and 1, are read from
+/// the red channel of their texture.
+pub struct Principled {
+    pub base_color: Arc<dyn Texture>,
+    /// 0 for a dielectric, 1 for a metal.
+    pub metallic: Arc<dyn Texture>,
+    /// Roughness of the specular and transmission lobes.
+    pub roughness: Arc<dyn Texture>,
+    /// Reflectance of dielectrics at normal incidence, with 0.5 for the usual 4%.
+    pub specular: Arc<dyn Texture>,
+    /// How much the specular reflection of dielectrics takes the hue of the base color.
+    pub specular_tint: Arc<dyn Texture>,
+    /// Extra reflection at grazing angles, for cloth.
+    pub sheen: Arc<dyn Texture>,
+    /// Strength of a second, glossy specular layer on top of the material.
+    pub clearcoat: Arc<dyn Texture>,
+    /// 0 for a satin coat, 1 for a glossy one.
+    pub clearcoat_gloss: Arc<dyn Texture>,
+    /// 0 for an opaque surface, 1 for glass.
+    pub transmission: Arc<dyn Texture>,
+    /// Index of refraction of the transmission.
+    pub ior: f64,
+    /// Light emitted by the front face of the surface, if any.
+    pub emission: Option<Arc<dyn Texture>>,
+    /// Identifies the inside of the material in the medium stack of the rays.
+    medium_id: usize,
+}
+
+/// The parameters of a [`Principled`] material at one point of the surface.
+struct PrincipledLobes {
+    base_color: Color,
+    specular_color: Color,
+    sheen: f64,
+    clearcoat: f64,
+    clearcoat_alpha: f64,
+    distribution: TrowbridgeReitz,
+    roughness: f64,
+    // Weights of the diffuse, specular, clearcoat and transmission lobes.
+    weights: [f64; 4],
+}
+
+impl Principled {
+    /// A rough dielectric of the given color.
+    pub fn new(base_color: Color) -> Self {
+        let gray = |value: f64| -> Arc<dyn Texture> { Arc::new(SolidColor::gray(value)) };
+        Self {
+            base_color: Arc::new(SolidColor::new(base_color)),
+            metallic: gray(0.),
+            roughness: gray(0.5),
+            specular: gray(0.5),
+            specular_tint: gray(0.),
+            sheen: gray(0.),
+            clearcoat: gray(0.),
+            clearcoat_gloss: gray(1.),
+            transmission: gray(0.),
+            ior: 1.5,
+            emission: None,
+            medium_id: NEXT_MEDIUM_ID.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    fn lobes(&self, rec: &HitRecord) -> PrincipledLobes {
+        let scalar =
+            |texture: &Arc<dyn Texture>| texture.value(rec.u, rec.v, rec.p).r().clamp(0., 1.);
+        let base_color = self.base_color.value(rec.u, rec.v, rec.p);
+        let metallic = scalar(&self.metallic);
+        let roughness = scalar(&self.roughness);
+        let transmission = scalar(&self.transmission);
+        let clearcoat = scalar(&self.clearcoat);
+
+        // The hue of the base color, without its brightness.
+        let luminance = 0.3 * base_color.r() + 0.6 * base_color.g() + 0.1 * base_color.b();
+        let white = Color::from(1, 1, 1);
+        let tint = if luminance > 0. {
+            base_color / luminance
         } else {
-            direction = unit_direction.refract_with(rec.normal, refraction_ratio);
+            white
+        };
+        let specular_tint = scalar(&self.specular_tint);
+        let dielectric_specular =
+            0.08 * scalar(&self.specular) * ((1. - specular_tint) * white + specular_tint * tint);
+        let specular_color = (1. - metallic) * dielectric_specular + metallic * base_color;
+
+        // Too smooth a surface would turn into a delta lobe, so the roughness is clamped.
+        let alpha = (roughness * roughness).max(1e-3);
+        let dielectric = (1. - metallic) * (1. - transmission);
+        let glass = (1. - metallic) * transmission;
+
+        PrincipledLobes {
+            base_color,
+            specular_color,
+            sheen: scalar(&self.sheen),
+            clearcoat,
+            clearcoat_alpha: 0.1 + (0.001 - 0.1) * scalar(&self.clearcoat_gloss),
+            distribution: TrowbridgeReitz::new(alpha, alpha),
+            roughness,
+            // The glass reflects light itself, so the specular lobe fades where it takes over.
+            weights: [dielectric, 1. - glass, 0.25 * clearcoat, glass],
         }
-        *scattered = Ray::new(rec.p, direction);
+    }
+
+    /// The lobe handling the transmission, reflecting and refracting like frosted glass.
+    fn glass(&self, lobes: &PrincipledLobes) -> RoughDielectric {
+        RoughDielectric {
+            dielectric: Dielectric {
+                medium: Medium::new(Ior::Constant(self.ior)),
+                film: None,
+                id: self.medium_id,
+            },
+            distribution: lobes.distribution,
+        }
+    }
+
+    /// Fraction of the light getting through the clear coat on its way in and out, which the
+    /// lobes beneath it are scaled by so the coat doesn't add energy.
+    fn under_clearcoat(lobes: &PrincipledLobes, cos_o: f64, cos_i: f64) -> f64 {
+        let transmitted =
+            |cos: f64| 1. - lobes.weights[2] * (0.04 + 0.96 * schlick_weight(cos.abs()));
+        transmitted(cos_o) * transmitted(cos_i)
+    }
+
+    /// Probability for `sample` to pick each lobe.
+    fn lobe_probabilities(lobes: &PrincipledLobes) -> [f64; 4] {
+        let total: f64 = lobes.weights.iter().sum();
+        lobes.weights.map(|weight| weight / total)
+    }
+}
+
+impl Material for Principled {
+    fn eval(&self, rec: &HitRecord, wo: Vec3, wi: Vec3) -> Color {
+        let lobes = self.lobes(rec);
+        let [diffuse_weight, specular_weight, clearcoat_weight, glass_weight] = lobes.weights;
+        let mut f = Color::new();
+        let mut coat = Color::new();
+
+        let uvw = Onb::new(rec.normal);
+        let (lo, li) = (uvw.to_local(wo), uvw.to_local(wi));
+        if lo.z() > 0. && li.z() > 0. {
+            let wh = (lo + li).unit_vector();
+            let cos_d = li.dot(wh);
+
+            // Burley's diffuse, with a retro-reflection that grows with the roughness.
+            let fd90 = 0.5 + 2. * lobes.roughness * cos_d * cos_d;
+            let fl = schlick_weight(li.z());
+            let fv = schlick_weight(lo.z());
+            let diffuse =
+                lobes.base_color / PI * ((1. + (fd90 - 1.) * fl) * (1. + (fd90 - 1.) * fv));
+            let sheen = lobes.sheen * schlick_weight(cos_d);
+            f += diffuse_weight * (diffuse + Color::from(sheen, sheen, sheen));
+
+            let white = Color::from(1, 1, 1);
+            let fresnel =
+                lobes.specular_color + (white - lobes.specular_color) * schlick_weight(lo.dot(wh));
+            let d = lobes.distribution.d(wh);
+            let g = lobes.distribution.g(lo, li);
+            f += specular_weight * fresnel * (d * g / (4. * lo.z() * li.z()));
+
+            if lobes.clearcoat > 0. {
+                let fresnel = 0.04 + 0.96 * schlick_weight(lo.dot(wh));
+                let d = gtr1(wh.z(), lobes.clearcoat_alpha);
+                let g = TrowbridgeReitz::new(0.25, 0.25).g(lo, li);
+                let value = clearcoat_weight * fresnel * d * g / (4. * lo.z() * li.z());
+                coat = Color::from(value, value, value);
+            }
+        }
+
+        if glass_weight > 0. {
+            f += glass_weight * lobes.base_color * self.glass(&lobes).eval(rec, wo, wi);
+        }
+        Self::under_clearcoat(&lobes, lo.z(), li.z()) * f + coat
+    }
+
+    fn sample(&self, r_in: &Ray, rec: &HitRecord) -> Option<BsdfSample> {
+        let lobes = self.lobes(rec);
+        let [diffuse, specular, clearcoat, glass] = Self::lobe_probabilities(&lobes);
+
+        let uvw = Onb::new(rec.normal);
+        let wo = -r_in.direction().unit_vector();
+        let lo = uvw.to_local(wo);
+        let xi = crate::random_float();
+        let wi = if xi < diffuse {
+            uvw.transform(Vec3::random_cosine_direction())
+        } else if xi < diffuse + specular {
+            let wm = lobes.distribution.sample_wm(lo);
+            uvw.transform((-lo).reflect_with(wm))
+        } else if xi < diffuse + specular + clearcoat {
+            let wm = uvw.transform(sample_gtr1(lobes.clearcoat_alpha));
+            (-wo).reflect_with(wm)
+        } else {
+            let sample = self.glass(&lobes).sample(r_in, rec)?;
+            if sample.is_delta {
+                // Glass that falls back to a smooth interface has no density to share with the
+                // other lobes, so its sample stands on its own.
+                let cos_i = sample.scattered.direction().unit_vector().dot(rec.normal);
+                let f = Self::under_clearcoat(&lobes, lo.z(), cos_i)
+                    * lobes.weights[3]
+                    * lobes.base_color
+                    * sample.f
+                    / glass;
+                return Some(BsdfSample { f, ..sample });
+            }
+            sample.scattered.direction().unit_vector()
+        };
+
+        let pdf = self.pdf(rec, wo, wi);
+        (pdf > 0.).then(|| BsdfSample {
+            scattered: r_in
+                .spawn(rec.p, wi)
+                .with_media(self.media_toward(r_in, rec, wi)),
+            f: self.eval(rec, wo, wi),
+            pdf,
+            is_delta: false,
+            is_spectral: false,
+        })
+    }
+
+    fn pdf(&self, rec: &HitRecord, wo: Vec3, wi: Vec3) -> f64 {
+        let lobes = self.lobes(rec);
+        let [diffuse, specular, clearcoat, glass] = Self::lobe_probabilities(&lobes);
+        let mut pdf = 0.;
+
+        let uvw = Onb::new(rec.normal);
+        let (lo, li) = (uvw.to_local(wo), uvw.to_local(wi));
+        if lo.z() > 0. && li.z() > 0. {
+            let wh = (lo + li).unit_vector();
+            pdf += diffuse * li.z() / PI;
+            pdf += specular * lobes.distribution.d_visible(lo, wh) / (4. * lo.dot(wh));
+            pdf += clearcoat * gtr1(wh.z(), lobes.clearcoat_alpha) * wh.z() / (4. * lo.dot(wh));
+        }
+
+        if glass > 0. {
+            pdf += glass * self.glass(&lobes).pdf(rec, wo, wi);
+        }
+        pdf
+    }
+
+    fn emitted(&self, r_in: &Ray, rec: &HitRecord) -> Color {
+        match &self.emission {
+            Some(emission) if rec.front_face => r_in.spectrum(emission.value(rec.u, rec.v, rec.p)),
+            _ => Color::new(),
+        }
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.emission.is_some()
+    }
+
+    fn media_toward(&self, r_in: &Ray, rec: &HitRecord, wi: Vec3) -> MediumStack {
+        let lobes = self.lobes(rec);
+        if lobes.weights[3] > 0. {
+            self.glass(&lobes).media_toward(r_in, rec, wi)
+        } else {
+            r_in.media().clone()
+        }
+    }
+}
+
+/// Weight of the grazing term of Schlick's Fresnel approximation.
+fn schlick_weight(cos_theta: f64) -> f64 {
+    (1. - cos_theta).clamp(0., 1.).powi(5)
+}
+
+/// The GTR1 distribution of microfacet normals used by the clear coat, whose long tail gives
+/// it a sharp highlight with a soft halo.
+fn gtr1(cos_theta_h: f64, alpha: f64) -> f64 {
+    if alpha >= 1. {
+        return 1. / PI;
+    }
+    let alpha2 = alpha * alpha;
+    let t = 1. + (alpha2 - 1.) * cos_theta_h * cos_theta_h;
+    (alpha2 - 1.) / (PI * alpha2.ln() * t)
+}
+
+/// Pick a microfacet normal around the z axis following `gtr1(cos_theta_h) * cos_theta_h`.
+fn sample_gtr1(alpha: f64) -> Vec3 {
+    let alpha2 = alpha * alpha;
+    let cos_theta = ((1. - alpha2.powf(1. - crate::random_float())) / (1. - alpha2))
+        .max(0.)
+        .sqrt();
+    let sin_theta = (1. - cos_theta * cos_theta).max(0.).sqrt();
+    let phi = 2. * PI * crate::random_float();
+    Vec3::from(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
+
+/// A blend of two materials, e.g. to add patches of rust to a metal.
+pub struct Mix {
+    first: Arc<dyn Material>,
+    second: Arc<dyn Material>,
+    mask: Arc<dyn Texture>, // Amount of `second`, read from the red channel
+}
 
-        return true;
+impl Mix {
+    /// Blend `amount` of `second` into `first`, with 0 for only `first` and 1 for only `second`.
+    pub fn new(first: Arc<dyn Material>, second: Arc<dyn Material>, amount: f64) -> Self {
+        Self::with_mask(first, second, Arc::new(SolidColor::gray(amount)))
     }
+
+    /// Blend `first` and `second` following the red channel of `mask`, with 0 for only `first`
+    /// and 1 for only `second`.
+    pub fn with_mask(
+        first: Arc<dyn Material>,
+        second: Arc<dyn Material>,
+        mask: Arc<dyn Texture>,
+    ) -> Self {
+        Self {
+            first,
+            second,
+            mask,
+        }
+    }
+
+    fn amount(&self, rec: &HitRecord) -> f64 {
+        self.mask.value(rec.u, rec.v, rec.p).r().clamp(0., 1.)
+    }
+}
+
+impl Material for Mix {
+    fn eval(&self, rec: &HitRecord, wo: Vec3, wi: Vec3) -> Color {
+        let t = self.amount(rec);
+        (1. - t) * self.first.eval(rec, wo, wi) + t * self.second.eval(rec, wo, wi)
+    }
+
+    fn sample(&self, r_in: &Ray, rec: &HitRecord) -> Option<BsdfSample> {
+        // Sample one of the materials in proportion to its share.
+        let t = self.amount(rec);
+        let material = if crate::random_float() < t {
+            &self.second
+        } else {
+            &self.first
+        };
+        let sample = material.sample(r_in, rec)?;
+        if sample.is_delta {
+            // Picking the material cancels out its share of the blend.
+            return Some(sample);
+        }
+
+        // Either material could have picked the direction.
+        let wo = -r_in.direction().unit_vector();
+        let wi = sample.scattered.direction().unit_vector();
+        let pdf = self.pdf(rec, wo, wi);
+        (pdf > 0.).then(|| BsdfSample {
+            f: self.eval(rec, wo, wi),
+            pdf,
+            ..sample
+        })
+    }
+
+    fn pdf(&self, rec: &HitRecord, wo: Vec3, wi: Vec3) -> f64 {
+        let t = self.amount(rec);
+        (1. - t) * self.first.pdf(rec, wo, wi) + t * self.second.pdf(rec, wo, wi)
+    }
+
+    fn is_delta(&self) -> bool {
+        self.first.is_delta() && self.second.is_delta()
+    }
+
+    fn emitted(&self, r_in: &Ray, rec: &HitRecord) -> Color {
+        let t = self.amount(rec);
+        (1. - t) * self.first.emitted(r_in, rec) + t * self.second.emitted(r_in, rec)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.first.is_emissive() || self.second.is_emissive()
+    }
+
+    fn shade(&self, rec: &mut HitRecord) {
+        // A surface has a single shading normal, so the material with the largest share decides.
+        if self.amount(rec) < 0.5 {
+            self.first.shade(rec);
+        } else {
+            self.second.shade(rec);
+        }
+    }
+
+    fn media_toward(&self, r_in: &Ray, rec: &HitRecord, wi: Vec3) -> MediumStack {
+        // A path can only be in one medium, so the material with the largest share decides.
+        if self.amount(rec) < 0.5 {
+            self.first.media_toward(r_in, rec, wi)
+        } else {
+            self.second.media_toward(r_in, rec, wi)
+        }
+    }
+}
+
+/// A material under a smooth clear coat, like car paint or varnished wood. The coat reflects
+/// part of the light, and the base material only gets what crosses the coat both ways.
+pub struct Coated {
+    base: Arc<dyn Material>,
+    ior: f64, // Index of refraction of the coat
+}
+
+impl Coated {
+    pub fn new(base: Arc<dyn Material>, ior: f64) -> Self {
+        Self { base, ior }
+    }
+
+    /// The fraction of the light reflected by the coat at an angle whose cosine is `cos_theta`.
+    fn reflectance(&self, cos_theta: f64) -> f64 {
+        fresnel::dielectric(cos_theta.abs(), self.ior)
+    }
+}
+
+impl Material for Coated {
+    fn eval(&self, rec: &HitRecord, wo: Vec3, wi: Vec3) -> Color {
+        let transmitted = (1. - self.reflectance(wo.dot(rec.normal)))
+            * (1. - self.reflectance(wi.dot(rec.normal)));
+        transmitted * self.base.eval(rec, wo, wi)
+    }
+
+    fn sample(&self, r_in: &Ray, rec: &HitRecord) -> Option<BsdfSample> {
+        // Reflect off the coat with the probability given by its Fresnel reflectance, which
+        // cancels out with the reflectance itself.
+        let wo = -r_in.direction().unit_vector();
+        if crate::random_float() < self.reflectance(wo.dot(rec.normal)) {
+            return Some(BsdfSample {
+                scattered: r_in.spawn(rec.p, (-wo).reflect_with(rec.normal)),
+                f: Color::from(1, 1, 1),
+                pdf: 1.,
+                is_delta: true,
+                is_spectral: false,
+            });
+        }
+
+        let sample = self.base.sample(r_in, rec)?;
+        let wi = sample.scattered.direction().unit_vector();
+        if sample.is_delta {
+            // Only the light getting out through the coat is left.
+            let f = (1. - self.reflectance(wi.dot(rec.normal))) * sample.f;
+            return Some(BsdfSample { f, ..sample });
+        }
+
+        let pdf = self.pdf(rec, wo, wi);
+        (pdf > 0.).then(|| BsdfSample {
+            f: self.eval(rec, wo, wi),
+            pdf,
+            ..sample
+        })
+    }
+
+    fn pdf(&self, rec: &HitRecord, wo: Vec3, wi: Vec3) -> f64 {
+        (1. - self.reflectance(wo.dot(rec.normal))) * self.base.pdf(rec, wo, wi)
+    }
+
+    fn is_delta(&self) -> bool {
+        self.base.is_delta()
+    }
+
+    fn emitted(&self, r_in: &Ray, rec: &HitRecord) -> Color {
+        let wo = -r_in.direction().unit_vector();
+        (1. - self.reflectance(wo.dot(rec.normal))) * self.base.emitted(r_in, rec)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.base.is_emissive()
+    }
+
+    fn shade(&self, rec: &mut HitRecord) {
+        self.base.shade(rec);
+    }
+
+    fn media_toward(&self, r_in: &Ray, rec: &HitRecord, wi: Vec3) -> MediumStack {
+        self.base.media_toward(r_in, rec, wi)
+    }
+}
+
+/// Details added to the surface of `base` by tilting its shading normal, following a normal map
+/// in tangent space: red goes along `dpdu`, green along `dpdv` and blue along the normal.
+pub struct NormalMap {
+    base: Arc<dyn Material>,
+    map: Arc<dyn Texture>,
+}
+
+impl NormalMap {
+    /// `map` holds the normals remapped from `[-1, 1]` to `[0, 1]`, without gamma encoding.
+    pub fn new(base: Arc<dyn Material>, map: Arc<dyn Texture>) -> Self {
+        Self { base, map }
+    }
+
+    pub fn load(base: Arc<dyn Material>, path: impl AsRef<Path>) -> io::Result<Self> {
+        let map = ImageTexture::linear(Image::load(path)?);
+        Ok(Self::new(base, Arc::new(map)))
+    }
+
+    fn tilt(&self, rec: &mut HitRecord) {
+        let outward = outward_normal(rec);
+        let uvw = Onb::with_tangent(outward, rec.dpdu);
+        // Keep the map's green axis along `v` whichever way the tangents turn.
+        let bitangent = if uvw.v().dot(rec.dpdv) < 0. {
+            -uvw.v()
+        } else {
+            uvw.v()
+        };
+        let local = 2. * self.map.value(rec.u, rec.v, rec.p) - Color::from(1, 1, 1);
+        let normal = local.x() * uvw.u() + local.y() * bitangent + local.z() * uvw.w();
+        set_shading_normal(rec, normal);
+    }
+}
+
+impl Material for NormalMap {
+    fn eval(&self, rec: &HitRecord, wo: Vec3, wi: Vec3) -> Color {
+        self.base.eval(rec, wo, wi)
+    }
+
+    fn sample(&self, r_in: &Ray, rec: &HitRecord) -> Option<BsdfSample> {
+        self.base.sample(r_in, rec)
+    }
+
+    fn pdf(&self, rec: &HitRecord, wo: Vec3, wi: Vec3) -> f64 {
+        self.base.pdf(rec, wo, wi)
+    }
+
+    fn is_delta(&self) -> bool {
+        self.base.is_delta()
+    }
+
+    fn emitted(&self, r_in: &Ray, rec: &HitRecord) -> Color {
+        self.base.emitted(r_in, rec)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.base.is_emissive()
+    }
+
+    fn shade(&self, rec: &mut HitRecord) {
+        self.tilt(rec);
+        self.base.shade(rec);
+    }
+
+    fn media_toward(&self, r_in: &Ray, rec: &HitRecord, wi: Vec3) -> MediumStack {
+        self.base.media_toward(r_in, rec, wi)
+    }
+}
+
+/// Bumps on the surface of `base`, shading it as if it was displaced along its normal by the
+/// red channel of `height` times `scale`.
+pub struct BumpMap {
+    base: Arc<dyn Material>,
+    height: Arc<dyn Texture>,
+    scale: f64,
+}
+
+impl BumpMap {
+    pub fn new(base: Arc<dyn Material>, height: Arc<dyn Texture>, scale: f64) -> Self {
+        Self {
+            base,
+            height,
+            scale,
+        }
+    }
+
+    fn tilt(&self, rec: &mut HitRecord) {
+        // Finite differences of the height along the texture coordinates.
+        const DELTA: f64 = 0.0005;
+        let height = |u: f64, v: f64, p: Vec3| self.scale * self.height.value(u, v, p).r();
+        let h = height(rec.u, rec.v, rec.p);
+        let dhdu = (height(rec.u + DELTA, rec.v, rec.p + DELTA * rec.dpdu) - h) / DELTA;
+        let dhdv = (height(rec.u, rec.v + DELTA, rec.p + DELTA * rec.dpdv) - h) / DELTA;
+
+        let outward = outward_normal(rec);
+        let dpdu = rec.dpdu + dhdu * outward;
+        let dpdv = rec.dpdv + dhdv * outward;
+        let mut normal = dpdu.cross(dpdv);
+        if normal.dot(outward) < 0. {
+            normal = -normal;
+        }
+        if !normal.near_zero() {
+            set_shading_normal(rec, normal);
+        }
+    }
+}
+
+impl Material for BumpMap {
+    fn eval(&self, rec: &HitRecord, wo: Vec3, wi: Vec3) -> Color {
+        self.base.eval(rec, wo, wi)
+    }
+
+    fn sample(&self, r_in: &Ray, rec: &HitRecord) -> Option<BsdfSample> {
+        self.base.sample(r_in, rec)
+    }
+
+    fn pdf(&self, rec: &HitRecord, wo: Vec3, wi: Vec3) -> f64 {
+        self.base.pdf(rec, wo, wi)
+    }
+
+    fn is_delta(&self) -> bool {
+        self.base.is_delta()
+    }
+
+    fn emitted(&self, r_in: &Ray, rec: &HitRecord) -> Color {
+        self.base.emitted(r_in, rec)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.base.is_emissive()
+    }
+
+    fn shade(&self, rec: &mut HitRecord) {
+        self.tilt(rec);
+        self.base.shade(rec);
+    }
+
+    fn media_toward(&self, r_in: &Ray, rec: &HitRecord, wi: Vec3) -> MediumStack {
+        self.base.media_toward(r_in, rec, wi)
+    }
+}
+
+/// The shading normal of `rec` on the outer side of the surface.
+fn outward_normal(rec: &HitRecord) -> Vec3 {
+    if rec.front_face {
+        rec.normal
+    } else {
+        -rec.normal
+    }
+}
+
+/// Shade `rec` with `outward_normal` instead, with tangents made perpendicular to it.
+fn set_shading_normal(rec: &mut HitRecord, outward_normal: Vec3) {
+    rec.set_shading_normal(outward_normal.unit_vector());
+    let normal = rec.normal;
+    rec.dpdu = rec.dpdu - rec.dpdu.dot(normal) * normal;
+    rec.dpdv = rec.dpdv - rec.dpdv.dot(normal) * normal;
 }
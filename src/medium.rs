@@ -0,0 +1,108 @@
+use crate::{material::Ior, Color};
+
+/// What fills the inside of a dielectric object.
+#[derive(Copy, Clone, Debug)]
+pub struct Medium {
+    pub ior: Ior,
+    /// Fraction of the light absorbed per unit of distance, per channel, following the
+    /// Beer-Lambert law.
+    pub absorption: Color,
+    /// Fraction of the light scattered in a random direction per unit of distance, per channel.
+    pub scattering: Color,
+    /// Where the volumes of several objects overlap, the medium with the highest priority fills
+    /// the overlap. This lets e.g. the volume of a liquid slightly overlap its glass so that
+    /// they have no gap of air in between.
+    pub priority: u32,
+}
+
+impl Medium {
+    /// A clear medium with the lowest priority.
+    pub fn new(ior: Ior) -> Self {
+        Self {
+            ior,
+            absorption: Color::new(),
+            scattering: Color::new(),
+            priority: 0,
+        }
+    }
+
+    /// A translucent medium, like skin, wax or marble, whose surface looks like `color` and where
+    /// light travels `radius` on average before scattering, per channel.
+    pub fn subsurface(ior: Ior, color: Color, radius: Color) -> Self {
+        // Van de Hulst's inversion of the albedo of a semi-infinite slab gives the fraction of
+        // light scattered at each event that makes the surface look like `color`.
+        let single_scattering_albedo = color.map(|albedo| {
+            let a = albedo.clamp(0., 1.);
+            1. - (4.09712 + 4.20863 * a - (9.59217 + 41.6808 * a + 17.7126 * a * a).sqrt()).powi(2)
+        });
+        let extinction = radius.map(|radius| 1. / radius.max(1e-6));
+        let scattering = single_scattering_albedo * extinction;
+        Self {
+            absorption: extinction - scattering,
+            scattering,
+            ..Self::new(ior)
+        }
+    }
+
+    /// Whether light can scatter inside the medium, and not only be absorbed.
+    pub fn is_scattering(&self) -> bool {
+        self.scattering != Color::new()
+    }
+
+    /// The fraction of light neither absorbed nor scattered away after traveling `distance`
+    /// through the medium.
+    pub fn transmittance(&self, distance: f64) -> Color {
+        // Checking for clear channels avoids a NaN for infinite distances.
+        (self.absorption + self.scattering).map(|extinction| {
+            if extinction == 0. {
+                1.
+            } else {
+                (-extinction * distance).exp()
+            }
+        })
+    }
+}
+
+/// The media a path is inside of, each tagged with an identifier of the object it belongs to.
+#[derive(Clone, Debug, Default)]
+pub struct MediumStack {
+    entries: Vec<(usize, Medium)>,
+}
+
+impl MediumStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The medium filling the space the path is in: the one with the highest priority, or the
+    /// latest entered among those with the same priority. `None` stands for air.
+    pub fn current(&self) -> Option<&Medium> {
+        self.entries
+            .iter()
+            .max_by_key(|(_, medium)| medium.priority)
+            .map(|(_, medium)| medium)
+    }
+
+    /// Record that the path entered `medium`.
+    pub fn push(&mut self, id: usize, medium: Medium) {
+        self.entries.push((id, medium));
+    }
+
+    /// Record that the path left the latest entered medium identified by `id`.
+    pub fn remove(&mut self, id: usize) {
+        if let Some(i) = self.entries.iter().rposition(|(entry, _)| *entry == id) {
+            self.entries.remove(i);
+        }
+    }
+
+    /// The fraction of light left after traveling `distance` through the current medium.
+    pub fn transmittance(&self, distance: f64) -> Color {
+        self.current().map_or(Color::from(1, 1, 1), |medium| {
+            medium.transmittance(distance)
+        })
+    }
+}
@@ -0,0 +1,102 @@
+use std::f64::consts::PI;
+
+use crate::{random_float, Vec3};
+
+/// The Trowbridge-Reitz (GGX) distribution of microfacet normals.
+///
+/// Directions are in the local frame of the surface, with the normal along z and the
+/// roughness `alpha_x` along x.
+#[derive(Copy, Clone, Debug)]
+pub struct TrowbridgeReitz {
+    pub alpha_x: f64,
+    pub alpha_y: f64,
+}
+
+impl TrowbridgeReitz {
+    pub fn new(alpha_x: f64, alpha_y: f64) -> Self {
+        Self { alpha_x, alpha_y }
+    }
+
+    /// The distribution for a perceptual `roughness` between 0 and 1, with `alpha` growing as
+    /// its square so that roughness changes look even.
+    pub fn from_roughness(roughness_x: f64, roughness_y: f64) -> Self {
+        let alpha = |roughness: f64| roughness.clamp(0., 1.).powi(2);
+        Self::new(alpha(roughness_x), alpha(roughness_y))
+    }
+
+    /// Whether the surface is so smooth that it's best handled as a perfect specular one.
+    pub fn effectively_smooth(&self) -> bool {
+        self.alpha_x.max(self.alpha_y) < 1e-3
+    }
+
+    /// Density of microfacets with normal `wm`, per unit of surface area and solid angle.
+    pub fn d(&self, wm: Vec3) -> f64 {
+        if wm.z() <= 0. {
+            return 0.;
+        }
+        let x = wm.x() / self.alpha_x;
+        let y = wm.y() / self.alpha_y;
+        let e = x * x + y * y + wm.z() * wm.z();
+        1. / (PI * self.alpha_x * self.alpha_y * e * e)
+    }
+
+    /// Smith's auxiliary function, measuring the microfacet area hidden when seen from `w`.
+    pub fn lambda(&self, w: Vec3) -> f64 {
+        if w.z() == 0. {
+            return f64::INFINITY;
+        }
+        let x = self.alpha_x * w.x();
+        let y = self.alpha_y * w.y();
+        let alpha2_tan2_theta = (x * x + y * y) / (w.z() * w.z());
+        ((1. + alpha2_tan2_theta).sqrt() - 1.) / 2.
+    }
+
+    /// Fraction of the microfacets visible from `w`.
+    pub fn g1(&self, w: Vec3) -> f64 {
+        1. / (1. + self.lambda(w))
+    }
+
+    /// Fraction of the microfacets visible from both `wo` and `wi`.
+    pub fn g(&self, wo: Vec3, wi: Vec3) -> f64 {
+        1. / (1. + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Density of the normals of the microfacets visible from `w`.
+    pub fn d_visible(&self, w: Vec3, wm: Vec3) -> f64 {
+        self.g1(w) / w.z().abs() * self.d(wm) * w.dot(wm).abs()
+    }
+
+    /// Pick the normal of a microfacet visible from `w`, following `d_visible`.
+    pub fn sample_wm(&self, w: Vec3) -> Vec3 {
+        // Stretch the view direction so the distribution becomes a hemisphere of radius one.
+        let mut wh = Vec3::from(self.alpha_x * w.x(), self.alpha_y * w.y(), w.z()).unit_vector();
+        if wh.z() < 0. {
+            wh = -wh;
+        }
+        let t1 = if wh.z() < 0.99999 {
+            Vec3::from(0, 0, 1).cross(wh).unit_vector()
+        } else {
+            Vec3::from(1, 0, 0)
+        };
+        let t2 = wh.cross(t1);
+
+        // Sample the projection of the visible half of the hemisphere, which is a disk with
+        // one half squashed depending on the view angle.
+        let r = random_float().sqrt();
+        let phi = 2. * PI * random_float();
+        let (px, py) = (r * phi.cos(), r * phi.sin());
+        let h = (1. - px * px).sqrt();
+        let s = (1. + wh.z()) / 2.;
+        let py = (1. - s) * h + s * py;
+        let pz = (1. - px * px - py * py).max(0.).sqrt();
+        let nh = px * t1 + py * t2 + pz * wh;
+
+        // Unstretch back to the microfacet normal.
+        Vec3::from(
+            self.alpha_x * nh.x(),
+            self.alpha_y * nh.y(),
+            nh.z().max(1e-6),
+        )
+        .unit_vector()
+    }
+}
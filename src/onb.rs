@@ -0,0 +1,58 @@
+use crate::Vec3;
+
+/// An orthonormal basis, used to go between world space and a frame around a direction.
+#[derive(Copy, Clone, Debug)]
+pub struct Onb {
+    axis: [Vec3; 3],
+}
+
+impl Onb {
+    /// Build a basis whose `w` axis points along `n`.
+    pub fn new(n: Vec3) -> Self {
+        let w = n.unit_vector();
+        let a = if w.x().abs() > 0.9 {
+            Vec3::from(0, 1, 0)
+        } else {
+            Vec3::from(1, 0, 0)
+        };
+        let v = w.cross(a).unit_vector();
+        let u = w.cross(v);
+        Self { axis: [u, v, w] }
+    }
+
+    /// Build a basis whose `w` axis points along `n` and whose `u` axis is the part of
+    /// `tangent` perpendicular to `n`, or any basis around `n` if there's no such part.
+    pub fn with_tangent(n: Vec3, tangent: Vec3) -> Self {
+        let w = n.unit_vector();
+        let tangent = tangent - tangent.dot(w) * w;
+        if tangent.length_squared() < 1e-16 {
+            return Self::new(n);
+        }
+        let u = tangent.unit_vector();
+        Self {
+            axis: [u, w.cross(u), w],
+        }
+    }
+
+    pub fn u(&self) -> Vec3 {
+        self.axis[0]
+    }
+
+    pub fn v(&self) -> Vec3 {
+        self.axis[1]
+    }
+
+    pub fn w(&self) -> Vec3 {
+        self.axis[2]
+    }
+
+    /// Transform from basis coordinates to world space.
+    pub fn transform(&self, v: Vec3) -> Vec3 {
+        v.x() * self.u() + v.y() * self.v() + v.z() * self.w()
+    }
+
+    /// Transform from world space to basis coordinates.
+    pub fn to_local(&self, v: Vec3) -> Vec3 {
+        Vec3::from(v.dot(self.u()), v.dot(self.v()), v.dot(self.w()))
+    }
+}
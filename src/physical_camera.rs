@@ -0,0 +1,69 @@
+/// The settings of a real camera, in the units photographers use.
+///
+/// When set on a [`Camera`](crate::Camera), these settings replace its `vfov`,
+/// `aspect_ratio`, `defocus_angle` and shutter interval, and scale the brightness of the image
+/// like a real exposure would. The scene radiance is then expected in candela per square meter.
+///
+/// Scene time runs from 0 to 1, which spans `frame_duration` seconds, so the shutter closes
+/// [`shutter_fraction`](Self::shutter_fraction) after the camera's `shutter_open`, clamped to 1.
+#[derive(Copy, Clone, Debug)]
+pub struct PhysicalCamera {
+    pub focal_length: f64,    // Focal length of the lens, in millimeters
+    pub sensor_width: f64,    // Width of the sensor, in millimeters
+    pub sensor_height: f64,   // Height of the sensor, in millimeters
+    pub f_number: f64,        // Focal length over the diameter of the aperture
+    pub shutter_time: f64,    // Time during which the shutter stays open, in seconds
+    pub frame_duration: f64,  // Length of the scene time range [0, 1], in seconds
+    pub iso: f64,             // Sensitivity of the sensor
+    pub units_per_meter: f64, // Size of a meter in scene units
+}
+
+impl Default for PhysicalCamera {
+    fn default() -> Self {
+        // A 50mm lens on a full frame sensor, exposed for a sunny day.
+        Self {
+            focal_length: 50.,
+            sensor_width: 36.,
+            sensor_height: 24.,
+            f_number: 16.,
+            shutter_time: 1. / 100.,
+            frame_duration: 1. / 24.,
+            iso: 100.,
+            units_per_meter: 1.,
+        }
+    }
+}
+
+impl PhysicalCamera {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Vertical field of view, in degrees.
+    pub fn vfov(&self) -> f64 {
+        (2. * (self.sensor_height / (2. * self.focal_length)).atan()).to_degrees()
+    }
+
+    pub fn aspect_ratio(&self) -> f64 {
+        self.sensor_width / self.sensor_height
+    }
+
+    /// Part of the scene time range [0, 1] during which the shutter stays open.
+    pub fn shutter_fraction(&self) -> f64 {
+        self.shutter_time / self.frame_duration
+    }
+
+    /// Radius of the aperture, in scene units.
+    pub fn aperture_radius(&self) -> f64 {
+        let radius_mm = self.focal_length / (2. * self.f_number);
+        radius_mm / 1000. * self.units_per_meter
+    }
+
+    /// Scale from scene luminance to pixel values, using the saturation based sensitivity of
+    /// the ISO 12232 standard: a luminance of `78 / (0.65 * iso) * f_number² / shutter_time`
+    /// exactly saturates the sensor.
+    pub fn exposure(&self) -> f64 {
+        const LENS_TRANSMITTANCE: f64 = 0.65;
+        LENS_TRANSMITTANCE * self.iso * self.shutter_time / (78. * self.f_number.powi(2))
+    }
+}
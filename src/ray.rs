@@ -1,16 +1,60 @@
-use crate::{Point3, Vec3};
+use crate::{spectrum::HeroWavelengths, Color, MediumStack, Point3, Vec3};
 
 #[derive(Clone, Default)]
 pub struct Ray {
     orig: Point3,
     dir: Vec3,
+    tm: f64,
+    wavelength: Option<f64>,
+    hero: Option<HeroWavelengths>,
+    media: MediumStack,
 }
 
 impl Ray {
     pub fn new(origin: Point3, direction: Vec3) -> Self {
+        Self::with_time(origin, direction, 0.)
+    }
+
+    pub fn with_time(origin: Point3, direction: Vec3, time: f64) -> Self {
         Ray {
             orig: origin,
             dir: direction,
+            tm: time,
+            wavelength: None,
+            hero: None,
+            media: MediumStack::new(),
+        }
+    }
+
+    /// The same ray, carrying light of a single `wavelength` in nanometers.
+    pub fn with_wavelength(mut self, wavelength: f64) -> Self {
+        self.wavelength = Some(wavelength);
+        self
+    }
+
+    /// The same ray, carrying spectral colors at the wavelengths of `hero`.
+    pub fn with_hero_wavelengths(mut self, hero: HeroWavelengths) -> Self {
+        self.hero = Some(hero);
+        self
+    }
+
+    /// The same ray, traveling inside `media`.
+    pub fn with_media(mut self, media: MediumStack) -> Self {
+        self.media = media;
+        self
+    }
+
+    /// Create a ray leaving `origin` toward `direction` that belongs to the same path as this
+    /// one, i.e. it happens at the same time, carries the same wavelength and travels through the
+    /// same media.
+    pub fn spawn(&self, origin: Point3, direction: Vec3) -> Self {
+        Self {
+            orig: origin,
+            dir: direction,
+            tm: self.tm,
+            wavelength: self.wavelength,
+            hero: self.hero,
+            media: self.media.clone(),
         }
     }
 
@@ -25,4 +69,29 @@ impl Ray {
     pub fn direction(&self) -> Vec3 {
         self.dir
     }
+
+    pub fn time(&self) -> f64 {
+        self.tm
+    }
+
+    /// The wavelength of the light carried by the ray, in nanometers, or `None` when it carries
+    /// every wavelength as an RGB color. Spectral rays give their hero wavelength.
+    pub fn wavelength(&self) -> Option<f64> {
+        self.hero.map(|hero| hero.hero()).or(self.wavelength)
+    }
+
+    /// The wavelengths of a ray carrying spectral colors, or `None` for an RGB one.
+    pub fn hero_wavelengths(&self) -> Option<HeroWavelengths> {
+        self.hero
+    }
+
+    /// The media the ray travels through.
+    pub fn media(&self) -> &MediumStack {
+        &self.media
+    }
+
+    /// Convert `rgb` to the kind of color carried by the ray.
+    pub fn spectrum(&self, rgb: Color) -> Color {
+        self.hero.map_or(rgb, |hero| hero.upsample(rgb))
+    }
 }
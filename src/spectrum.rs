@@ -0,0 +1,210 @@
+//! Conversions between wavelengths of light and colors.
+
+use std::sync::OnceLock;
+
+use crate::{random_float, Color, Vec3};
+
+/// Shortest visible wavelength, in nanometers.
+pub const WAVELENGTH_MIN: f64 = 380.;
+/// Longest visible wavelength, in nanometers.
+pub const WAVELENGTH_MAX: f64 = 780.;
+
+/// The CIE 1931 color matching functions at `wavelength`, in nanometers, using the multi-lobe
+/// fit of Wyman, Sloan and Shirley.
+pub fn cie_xyz(wavelength: f64) -> Vec3 {
+    // A gaussian with a different width on each side of its mean.
+    let g = |mu: f64, sigma1: f64, sigma2: f64| {
+        let t = (wavelength - mu) / if wavelength < mu { sigma1 } else { sigma2 };
+        (-0.5 * t * t).exp()
+    };
+    Vec3::from(
+        1.056 * g(599.8, 37.9, 31.0) + 0.362 * g(442.0, 16.0, 26.7) - 0.065 * g(501.1, 20.4, 26.2),
+        0.821 * g(568.8, 46.9, 40.5) + 0.286 * g(530.9, 16.3, 31.1),
+        1.217 * g(437.0, 11.8, 36.0) + 0.681 * g(459.0, 26.0, 13.8),
+    )
+}
+
+/// Convert CIE XYZ to linear sRGB. Colors outside of the sRGB gamut get negative channels.
+pub fn xyz_to_srgb(xyz: Vec3) -> Color {
+    Color::from(
+        3.2406 * xyz.x() - 1.5372 * xyz.y() - 0.4986 * xyz.z(),
+        -0.9689 * xyz.x() + 1.8758 * xyz.y() + 0.0415 * xyz.z(),
+        0.0557 * xyz.x() - 0.2040 * xyz.y() + 1.0570 * xyz.z(),
+    )
+}
+
+/// A random visible wavelength, uniformly distributed.
+pub fn sample_wavelength() -> f64 {
+    WAVELENGTH_MIN + (WAVELENGTH_MAX - WAVELENGTH_MIN) * random_float()
+}
+
+/// The color carried by a path of a single wavelength picked by `sample_wavelength`, scaled so
+/// that white light stays white on average over all wavelengths.
+pub fn wavelength_weight(wavelength: f64) -> Color {
+    static WHITE: OnceLock<Color> = OnceLock::new();
+    let white = WHITE.get_or_init(|| {
+        let steps = 4000;
+        let step = (WAVELENGTH_MAX - WAVELENGTH_MIN) / steps as f64;
+        let mut sum = Vec3::new();
+        for i in 0..steps {
+            sum += cie_xyz(WAVELENGTH_MIN + (i as f64 + 0.5) * step);
+        }
+        xyz_to_srgb(sum / steps as f64)
+    });
+    xyz_to_srgb(cie_xyz(wavelength)) / *white
+}
+
+/// The wavelengths followed by a path in spectral mode: a randomly picked hero wavelength, and
+/// two others evenly spaced over the visible range. A spectral color holds the radiance at each
+/// of them in its three channels.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct HeroWavelengths {
+    hero: f64,
+    secondary_terminated: bool, // Whether only the hero wavelength is still followed
+}
+
+impl HeroWavelengths {
+    pub fn new(hero: f64) -> Self {
+        Self {
+            hero,
+            secondary_terminated: false,
+        }
+    }
+
+    pub fn sample() -> Self {
+        Self::new(sample_wavelength())
+    }
+
+    pub fn hero(&self) -> f64 {
+        self.hero
+    }
+
+    /// The three wavelengths, starting with the hero.
+    pub fn wavelengths(&self) -> [f64; 3] {
+        let range = WAVELENGTH_MAX - WAVELENGTH_MIN;
+        [0., 1., 2.].map(|i| {
+            WAVELENGTH_MIN + (self.hero - WAVELENGTH_MIN + i * range / 3.).rem_euclid(range)
+        })
+    }
+
+    /// Stop following the secondary wavelengths, e.g. after a dispersive refraction sent them
+    /// in other directions.
+    pub fn terminate_secondary(self) -> Self {
+        Self {
+            secondary_terminated: true,
+            ..self
+        }
+    }
+
+    pub fn is_secondary_terminated(&self) -> bool {
+        self.secondary_terminated
+    }
+
+    /// The spectral color of a smooth spectrum matching `rgb`.
+    pub fn upsample(&self, rgb: Color) -> Color {
+        let [a, b, c] = self
+            .wavelengths()
+            .map(|wavelength| rgb_to_spectrum(rgb, wavelength));
+        Color::from(a, b, c)
+    }
+
+    /// The contribution of the spectral color `radiance` to the RGB value of a pixel.
+    pub fn to_rgb(&self, radiance: Color) -> Color {
+        let [a, b, c] = self.wavelengths().map(wavelength_weight);
+        (radiance.x() * a + radiance.y() * b + radiance.z() * c) / 3.
+    }
+}
+
+/// The value at `wavelength` of a smooth spectrum matching `rgb`.
+pub fn rgb_to_spectrum(rgb: Color, wavelength: f64) -> f64 {
+    let [blue, green, red] = rgb_basis(wavelength);
+    blue * rgb.b() + green * rgb.g() + red * rgb.r()
+}
+
+/// The RGB color of light whose value at each `wavelength` is given by `spectrum`, evaluated at
+/// a few wavelengths only. A flat spectrum of 1 gives white exactly.
+pub fn spectrum_to_rgb(spectrum: impl Fn(f64) -> f64) -> Color {
+    const STEPS: usize = 16;
+    let step = (WAVELENGTH_MAX - WAVELENGTH_MIN) / STEPS as f64;
+    let mut sum = Color::new();
+    let mut white = Color::new();
+    for i in 0..STEPS {
+        let wavelength = WAVELENGTH_MIN + (i as f64 + 0.5) * step;
+        let weight = wavelength_weight(wavelength);
+        sum += spectrum(wavelength) * weight;
+        white += weight;
+    }
+    sum / white
+}
+
+/// Smooth spectra for pure blue, green and red, adding up to a flat spectrum, to turn an RGB
+/// color into a plausible spectrum.
+fn rgb_basis(wavelength: f64) -> [f64; 3] {
+    let smoothstep = |edge0: f64, edge1: f64| {
+        let t = ((wavelength - edge0) / (edge1 - edge0)).clamp(0., 1.);
+        t * t * (3. - 2. * t)
+    };
+    let blue = 1. - smoothstep(470., 510.);
+    let red = smoothstep(570., 610.);
+    [blue, 1. - blue - red, red]
+}
+
+/// The distribution of the power of a light source over the wavelengths.
+#[derive(Clone, Debug)]
+pub enum Spectrum {
+    /// The light of a black body at `temperature` kelvins, scaled so that its peak is 1.
+    Blackbody { temperature: f64 },
+    /// Values at increasing wavelengths in nanometers, linearly interpolated in between.
+    Tabulated(Vec<(f64, f64)>),
+}
+
+impl Spectrum {
+    /// A spectrum tabulated by `(wavelength, value)` pairs, in any order.
+    pub fn tabulated(mut samples: Vec<(f64, f64)>) -> Self {
+        samples.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Spectrum::Tabulated(samples)
+    }
+
+    /// The value at `wavelength`, in nanometers.
+    pub fn value(&self, wavelength: f64) -> f64 {
+        match self {
+            Spectrum::Blackbody { temperature } => {
+                // Wien's displacement law gives the wavelength of the peak.
+                let peak = 2.8977721e-3 / temperature * 1e9;
+                planck(wavelength, *temperature) / planck(peak, *temperature)
+            }
+            Spectrum::Tabulated(samples) => {
+                let i = samples.partition_point(|&(w, _)| w < wavelength);
+                match (samples.get(i.wrapping_sub(1)), samples.get(i)) {
+                    (Some(&(w0, v0)), Some(&(w1, v1))) => {
+                        v0 + (v1 - v0) * (wavelength - w0) / (w1 - w0)
+                    }
+                    (Some(&(_, v)), None) | (None, Some(&(_, v))) => v,
+                    (None, None) => 0.,
+                }
+            }
+        }
+    }
+
+    /// The RGB color of the light.
+    pub fn to_rgb(&self) -> Color {
+        let steps = 400;
+        let step = (WAVELENGTH_MAX - WAVELENGTH_MIN) / steps as f64;
+        let mut sum = Color::new();
+        for i in 0..steps {
+            let wavelength = WAVELENGTH_MIN + (i as f64 + 0.5) * step;
+            sum += self.value(wavelength) * wavelength_weight(wavelength);
+        }
+        sum / steps as f64
+    }
+}
+
+/// Planck's law: the spectral radiance of a black body at `temperature` kelvins, for a
+/// `wavelength` in nanometers.
+fn planck(wavelength: f64, temperature: f64) -> f64 {
+    const C: f64 = 299792458.;
+    const H: f64 = 6.62606957e-34;
+    const KB: f64 = 1.3806488e-23;
+    let lambda = wavelength * 1e-9;
+    2. * H * C * C / (lambda.powi(5) * ((H * C / (lambda * KB * temperature)).exp() - 1.))
+}
@@ -1,29 +1,57 @@
-use std::sync::Arc;
+use std::{f64::consts::PI, sync::Arc};
 
-use crate::{hittable::Hittable, HitRecord, Interval, Material, Point3, Ray};
+use crate::{
+    hittable::Hittable, random_float, Aabb, HitRecord, Interval, Material, Onb, Point3, Ray, Vec3,
+};
 
 pub struct Sphere {
     center: Point3,
     radius: f64,
     material: Arc<dyn Material>,
+    center_vec: Vec3, // Displacement of the center between time 0 and time 1
+    bbox: Aabb,
 }
 
 impl Sphere {
     pub fn new(center: Point3, radius: impl TryInto<f64>, material: Arc<dyn Material>) -> Self {
+        Self::new_moving(center, center, radius, material)
+    }
+
+    /// A sphere whose center moves linearly from `center0` at time 0 to `center1` at time 1.
+    pub fn new_moving(
+        center0: Point3,
+        center1: Point3,
+        radius: impl TryInto<f64>,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        let radius: f64 = radius
+            .try_into()
+            .map_err(|_| "could not parse radius")
+            .unwrap();
+        let rvec = Vec3::from(radius, radius, radius);
+        let box0 = Aabb::from_points(center0 - rvec, center0 + rvec);
+        let box1 = Aabb::from_points(center1 - rvec, center1 + rvec);
+
         Self {
-            center,
-            radius: radius
-                .try_into()
-                .map_err(|_| "could not parse radius")
-                .unwrap(),
+            center: center0,
+            radius,
             material,
+            center_vec: center1 - center0,
+            bbox: Aabb::enclosing(box0, box1),
         }
     }
+
+    fn center(&self, time: f64) -> Point3 {
+        // Linearly interpolate from center0 to center1 according to time, where t=0 yields
+        // center0, and t=1 yields center1.
+        self.center + time * self.center_vec
+    }
 }
 
 impl Hittable for Sphere {
     fn hit(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
-        let oc = r.origin() - self.center;
+        let center = self.center(r.time());
+        let oc = r.origin() - center;
         let a = r.direction().length_squared();
         let half_b = oc.dot(r.direction());
         let c = oc.length_squared() - self.radius.powi(2);
@@ -46,9 +74,85 @@ impl Hittable for Sphere {
         rec.t = root;
         rec.p = r.at(rec.t);
         rec.material = self.material.clone();
-        let outward_normal = (rec.p - self.center) / self.radius;
+        let outward_normal = (rec.p - center) / self.radius;
         rec.set_face_normal(r, outward_normal);
+        (rec.u, rec.v) = sphere_uv(outward_normal);
+        (rec.dpdu, rec.dpdv) = sphere_tangents(outward_normal, self.radius);
+
+        true
+    }
 
-        return true;
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
     }
+
+    fn is_emissive(&self) -> bool {
+        self.material.is_emissive()
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3, time: f64) -> f64 {
+        // From outside, the directions are uniformly distributed over the cone of directions
+        // the sphere covers. From inside, over every direction.
+        let to_center = self.center(time) - origin;
+        let distance_squared = to_center.length_squared();
+        if distance_squared <= self.radius * self.radius {
+            return 1. / (4. * PI);
+        }
+
+        let cos_theta_max = (1. - self.radius * self.radius / distance_squared).sqrt();
+        let cos_theta = direction.unit_vector().dot(to_center.unit_vector());
+        if cos_theta < cos_theta_max {
+            return 0.;
+        }
+        let solid_angle = 2. * PI * (1. - cos_theta_max);
+        1. / solid_angle
+    }
+
+    fn random(&self, origin: Point3, time: f64) -> Vec3 {
+        let direction = self.center(time) - origin;
+        let distance_squared = direction.length_squared();
+        if distance_squared <= self.radius * self.radius {
+            return Vec3::random_unit_vector();
+        }
+        let uvw = Onb::new(direction);
+        uvw.transform(random_to_sphere(self.radius, distance_squared))
+    }
+}
+
+/// Texture coordinates of point `p` on the unit sphere: `u` goes around the y axis from
+/// x=-1, and `v` goes from y=-1 to y=+1.
+fn sphere_uv(p: Point3) -> (f64, f64) {
+    let theta = (-p.y()).acos();
+    let phi = (-p.z()).atan2(p.x()) + PI;
+    (phi / (2. * PI), theta / PI)
+}
+
+/// Derivatives of the position on a sphere of `radius` along the texture coordinates given by
+/// `sphere_uv`, at the point of the unit sphere `p`.
+fn sphere_tangents(p: Point3, radius: f64) -> (Vec3, Vec3) {
+    let dpdu = 2. * PI * radius * Vec3::from(p.z(), 0, -p.x());
+    // `v` follows the meridians, which all meet at the poles.
+    let sin_theta = p.x().hypot(p.z()).max(1e-8);
+    let dpdv = PI
+        * radius
+        * Vec3::from(
+            -p.x() * p.y() / sin_theta,
+            sin_theta,
+            -p.y() * p.z() / sin_theta,
+        );
+    (dpdu, dpdv)
+}
+
+/// A random direction around the z axis within the cone covered by a sphere of `radius`, whose
+/// center is at `distance_squared` on the z axis.
+fn random_to_sphere(radius: f64, distance_squared: f64) -> Vec3 {
+    let r1 = random_float();
+    let r2 = random_float();
+    let z = 1. + r2 * ((1. - radius * radius / distance_squared).sqrt() - 1.);
+
+    let phi = 2. * PI * r1;
+    let x = phi.cos() * (1. - z * z).sqrt();
+    let y = phi.sin() * (1. - z * z).sqrt();
+
+    Vec3::from(x, y, z)
 }
@@ -0,0 +1,77 @@
+use std::{io, path::Path};
+
+use crate::{Color, Image, Point3};
+
+/// A color varying over a surface.
+pub trait Texture: Send + Sync {
+    /// The color at texture coordinates `u`, `v` and point `p` of a surface.
+    fn value(&self, u: f64, v: f64, p: Point3) -> Color;
+}
+
+/// The same color everywhere.
+#[derive(Copy, Clone, Debug)]
+pub struct SolidColor {
+    albedo: Color,
+}
+
+impl SolidColor {
+    pub fn new(albedo: Color) -> Self {
+        Self { albedo }
+    }
+
+    /// A gray level, mostly to drive a scalar parameter with a constant.
+    pub fn gray(value: f64) -> Self {
+        Self::new(Color::from(value, value, value))
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _p: Point3) -> Color {
+        self.albedo
+    }
+}
+
+/// An image wrapped over the surface, with `u` going left to right and `v` bottom to top.
+#[derive(Clone, Debug)]
+pub struct ImageTexture {
+    image: Image,
+    gamma_encoded: bool, // Whether the pixels are colors, rather than data like normals
+}
+
+impl ImageTexture {
+    pub fn new(image: Image) -> Self {
+        Self {
+            image,
+            gamma_encoded: true,
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Image::load(path).map(Self::new)
+    }
+
+    /// An image whose pixels are used as they are, for data like normal maps and heights.
+    pub fn linear(image: Image) -> Self {
+        Self {
+            image,
+            gamma_encoded: false,
+        }
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: Point3) -> Color {
+        let u = u.clamp(0., 1.);
+        let v = 1. - v.clamp(0., 1.);
+        let x = (u * self.image.width() as f64) as usize;
+        let y = (v * self.image.height() as f64) as usize;
+
+        // Colors are stored gamma encoded, undo the gamma 2 applied when writing them.
+        let pixel = self.image.pixel(x, y);
+        if self.gamma_encoded {
+            pixel * pixel
+        } else {
+            pixel
+        }
+    }
+}
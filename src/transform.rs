@@ -0,0 +1,221 @@
+use std::sync::Arc;
+
+use crate::{Aabb, HitRecord, Hittable, Interval, Point3, Ray, Vec3};
+
+/// Move an object by an offset, which can change linearly between time 0 and time 1.
+pub struct Translate {
+    object: Arc<dyn Hittable>,
+    offset: Vec3,
+    offset_vec: Vec3, // Change of the offset between time 0 and time 1
+    bbox: Aabb,
+}
+
+impl Translate {
+    pub fn new(object: Arc<dyn Hittable>, offset: Vec3) -> Self {
+        Self::new_moving(object, offset, offset)
+    }
+
+    pub fn new_moving(object: Arc<dyn Hittable>, offset0: Vec3, offset1: Vec3) -> Self {
+        let bbox = object.bounding_box();
+        let bbox = Aabb::enclosing(shift(bbox, offset0), shift(bbox, offset1));
+        Self {
+            object,
+            offset: offset0,
+            offset_vec: offset1 - offset0,
+            bbox,
+        }
+    }
+
+    fn offset(&self, time: f64) -> Vec3 {
+        self.offset + time * self.offset_vec
+    }
+}
+
+impl Hittable for Translate {
+    fn hit(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        let offset = self.offset(r.time());
+
+        // Move the ray backwards by the offset
+        let offset_r = r.spawn(r.origin() - offset, r.direction());
+
+        // Determine where (if any) an intersection occurs along the offset ray
+        if !self.object.hit(&offset_r, ray_t, rec) {
+            return false;
+        }
+
+        // Move the intersection point forwards by the offset
+        rec.p += offset;
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.object.is_emissive()
+    }
+
+    fn lights(&self) -> Vec<Arc<dyn Hittable>> {
+        // Lights must be sampled through the transform.
+        self.object
+            .lights()
+            .into_iter()
+            .map(|light| {
+                Arc::new(Self::new_moving(
+                    light,
+                    self.offset,
+                    self.offset + self.offset_vec,
+                )) as Arc<dyn Hittable>
+            })
+            .collect()
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3, time: f64) -> f64 {
+        self.object
+            .pdf_value(origin - self.offset(time), direction, time)
+    }
+
+    fn random(&self, origin: Point3, time: f64) -> Vec3 {
+        self.object.random(origin - self.offset(time), time)
+    }
+}
+
+fn shift(bbox: Aabb, offset: Vec3) -> Aabb {
+    Aabb::new(
+        Interval::from(bbox.x.min + offset.x(), bbox.x.max + offset.x()),
+        Interval::from(bbox.y.min + offset.y(), bbox.y.max + offset.y()),
+        Interval::from(bbox.z.min + offset.z(), bbox.z.max + offset.z()),
+    )
+}
+
+/// Rotate an object around the y axis by an angle in degrees, which can change linearly between
+/// time 0 and time 1.
+pub struct RotateY {
+    object: Arc<dyn Hittable>,
+    angle: f64,
+    angle_delta: f64, // Change of the angle between time 0 and time 1
+    bbox: Aabb,
+}
+
+impl RotateY {
+    pub fn new(object: Arc<dyn Hittable>, angle: f64) -> Self {
+        Self::new_moving(object, angle, angle)
+    }
+
+    pub fn new_moving(object: Arc<dyn Hittable>, angle0: f64, angle1: f64) -> Self {
+        let bbox = object.bounding_box();
+
+        let bbox = if angle0 == angle1 {
+            let (sin_theta, cos_theta) = angle0.to_radians().sin_cos();
+            let mut rotated = Aabb::default();
+            for x in [bbox.x.min, bbox.x.max] {
+                for y in [bbox.y.min, bbox.y.max] {
+                    for z in [bbox.z.min, bbox.z.max] {
+                        let corner = rotate(Point3::from(x, y, z), sin_theta, cos_theta);
+                        rotated = Aabb::enclosing(rotated, Aabb::from_points(corner, corner));
+                    }
+                }
+            }
+            rotated
+        } else {
+            // While spinning, the corners of the box sweep arcs that can bulge past the rotated
+            // boxes at both ends, so bound the cylinder they stay in instead.
+            let radius = [bbox.x.min, bbox.x.max]
+                .into_iter()
+                .flat_map(|x| [bbox.z.min, bbox.z.max].map(|z| x.hypot(z)))
+                .fold(0., f64::max);
+            Aabb::new(
+                Interval::from(-radius, radius),
+                bbox.y,
+                Interval::from(-radius, radius),
+            )
+        };
+
+        Self {
+            object,
+            angle: angle0,
+            angle_delta: angle1 - angle0,
+            bbox,
+        }
+    }
+
+    fn sin_cos(&self, time: f64) -> (f64, f64) {
+        (self.angle + time * self.angle_delta)
+            .to_radians()
+            .sin_cos()
+    }
+}
+
+impl Hittable for RotateY {
+    fn hit(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        let (sin_theta, cos_theta) = self.sin_cos(r.time());
+
+        // Change the ray from world space to object space
+        let origin = rotate(r.origin(), -sin_theta, cos_theta);
+        let direction = rotate(r.direction(), -sin_theta, cos_theta);
+        let rotated_r = r.spawn(origin, direction);
+
+        // Determine where (if any) an intersection occurs in object space
+        if !self.object.hit(&rotated_r, ray_t, rec) {
+            return false;
+        }
+
+        // Change the intersection point, normals and tangents from object space to world space
+        rec.p = rotate(rec.p, sin_theta, cos_theta);
+        rec.normal = rotate(rec.normal, sin_theta, cos_theta);
+        rec.geometric_normal = rotate(rec.geometric_normal, sin_theta, cos_theta);
+        rec.dpdu = rotate(rec.dpdu, sin_theta, cos_theta);
+        rec.dpdv = rotate(rec.dpdv, sin_theta, cos_theta);
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.object.is_emissive()
+    }
+
+    fn lights(&self) -> Vec<Arc<dyn Hittable>> {
+        // Lights must be sampled through the transform.
+        self.object
+            .lights()
+            .into_iter()
+            .map(|light| {
+                Arc::new(Self::new_moving(
+                    light,
+                    self.angle,
+                    self.angle + self.angle_delta,
+                )) as Arc<dyn Hittable>
+            })
+            .collect()
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3, time: f64) -> f64 {
+        let (sin_theta, cos_theta) = self.sin_cos(time);
+        self.object.pdf_value(
+            rotate(origin, -sin_theta, cos_theta),
+            rotate(direction, -sin_theta, cos_theta),
+            time,
+        )
+    }
+
+    fn random(&self, origin: Point3, time: f64) -> Vec3 {
+        let (sin_theta, cos_theta) = self.sin_cos(time);
+        let direction = self
+            .object
+            .random(rotate(origin, -sin_theta, cos_theta), time);
+        rotate(direction, sin_theta, cos_theta)
+    }
+}
+
+/// Rotate `p` around the y axis by the angle whose sine and cosine are given.
+fn rotate(p: Vec3, sin_theta: f64, cos_theta: f64) -> Vec3 {
+    Vec3::from(
+        cos_theta * p.x() + sin_theta * p.z(),
+        p.y(),
+        -sin_theta * p.x() + cos_theta * p.z(),
+    )
+}
@@ -89,6 +89,15 @@ impl<const N: usize> Vec<N> {
         ret
     }
 
+    /// Apply `f` to every component.
+    pub fn map(self, f: impl FnMut(f64) -> f64) -> Self {
+        Self(self.0.map(f))
+    }
+
+    pub fn max_component(self) -> f64 {
+        self.0.iter().copied().fold(f64::NEG_INFINITY, f64::max)
+    }
+
     pub fn near_zero(self) -> bool {
         // Return true if the vector is close to zero in all dimensions.
         let s = 1e-8;
@@ -148,6 +157,20 @@ impl Vec<3> {
         ])
     }
 
+    /// A random direction around the z axis, following a cosine distribution.
+    pub fn random_cosine_direction() -> Self {
+        let mut rng = rand::thread_rng();
+        let r1: f64 = rng.gen();
+        let r2: f64 = rng.gen();
+
+        let phi = 2. * std::f64::consts::PI * r1;
+        let x = phi.cos() * r2.sqrt();
+        let y = phi.sin() * r2.sqrt();
+        let z = (1. - r2).sqrt();
+
+        Self::from(x, y, z)
+    }
+
     pub fn random_in_unit_disk() -> Self {
         let mut rng = rand::thread_rng();
         loop {
@@ -258,6 +281,23 @@ impl<const N: usize> ops::Div<Vec<N>> for f64 {
     }
 }
 
+impl<const N: usize> ops::Div for Vec<N> {
+    type Output = Vec<N>;
+
+    fn div(mut self, rhs: Self) -> Self::Output {
+        self /= rhs;
+        self
+    }
+}
+
+impl<const N: usize> ops::DivAssign for Vec<N> {
+    fn div_assign(&mut self, rhs: Self) {
+        for n in 0..N {
+            self[n] /= rhs[n];
+        }
+    }
+}
+
 impl<const N: usize> ops::DivAssign<f64> for Vec<N> {
     fn div_assign(&mut self, rhs: f64) {
         self.0 = self.0.map(|n| n / rhs);
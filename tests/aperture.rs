@@ -0,0 +1,74 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use raytracing::{Aperture, ApertureMask, Image};
+
+const SAMPLES: usize = 200_000;
+
+#[test]
+fn polygonal_aperture_is_uniform_over_its_shape() {
+    let blades = 6;
+    let rotation = 15.;
+    let aperture = Aperture::Polygonal { blades, rotation };
+
+    let step = 2. * PI / blades as f64;
+    let apothem = (step / 2.).cos();
+    let mut per_side = vec![0usize; blades];
+    let mut in_incircle = 0;
+
+    for _ in 0..SAMPLES {
+        let p = aperture.sample();
+        assert_eq!(p.z(), 0.);
+
+        // Every point lies inside the polygon: its projection on the direction of the middle of
+        // its side is at most the apothem.
+        let angle = (p.y().atan2(p.x()) - rotation.to_radians()).rem_euclid(2. * PI);
+        let side = ((angle / step) as usize).min(blades - 1);
+        let middle = rotation.to_radians() + (side as f64 + 0.5) * step;
+        assert!(p.x() * middle.cos() + p.y() * middle.sin() <= apothem + 1e-9);
+
+        per_side[side] += 1;
+        if p.length() < apothem {
+            in_incircle += 1;
+        }
+    }
+
+    // Each side of the polygon covers the same area.
+    for count in per_side {
+        let ratio = count as f64 / SAMPLES as f64;
+        assert!((ratio - 1. / blades as f64).abs() < 0.01, "{ratio}");
+    }
+
+    // The share of points inside the inscribed circle matches its share of the area.
+    let polygon_area = blades as f64 * apothem * (step / 2.).sin();
+    let expected = PI * apothem * apothem / polygon_area;
+    let ratio = in_incircle as f64 / SAMPLES as f64;
+    assert!((ratio - expected).abs() < 0.01, "{ratio} != {expected}");
+}
+
+#[test]
+fn mask_aperture_follows_the_image() {
+    // A 4x2 mask: the left half is opaque, then a half-transparent and a fully transparent
+    // column.
+    let image = Image::parse(b"P2\n4 2\n2\n0 0 1 2\n0 0 1 2\n").unwrap();
+    let aperture = Aperture::Mask(Arc::new(ApertureMask::from_image(&image).unwrap()));
+
+    let mut in_last_column = 0;
+    for _ in 0..SAMPLES {
+        let p = aperture.sample();
+        // The image spans [-1, 1] horizontally and [-0.5, 0.5] vertically.
+        assert!((0. ..=1.).contains(&p.x()), "{p}");
+        assert!((-0.5..=0.5).contains(&p.y()), "{p}");
+        if p.x() > 0.5 {
+            in_last_column += 1;
+        }
+    }
+
+    let ratio = in_last_column as f64 / SAMPLES as f64;
+    assert!((ratio - 2. / 3.).abs() < 0.01, "{ratio}");
+}
+
+#[test]
+fn black_mask_is_rejected() {
+    let image = Image::parse(b"P2\n2 2\n255\n0 0 0 0\n").unwrap();
+    assert!(ApertureMask::from_image(&image).is_err());
+}
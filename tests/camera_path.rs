@@ -0,0 +1,70 @@
+use raytracing::{CameraPath, Keyframe, Point3, Vec3};
+
+fn keyframe(time: f64, lookfrom: Point3, vfov: f64) -> Keyframe {
+    Keyframe {
+        time,
+        lookfrom,
+        lookat: Point3::new(),
+        vup: Vec3::from(0, 1, 0),
+        vfov,
+        focus_dist: 10.,
+    }
+}
+
+fn assert_close(a: Vec3, b: Vec3) {
+    assert!((a - b).near_zero(), "{a} != {b}");
+}
+
+#[test]
+fn paths_pass_through_their_keyframes() {
+    let keyframes = [
+        keyframe(0., Point3::from(0, 0, 10), 40.),
+        keyframe(1., Point3::from(5, 2, 8), 30.),
+        keyframe(3., Point3::from(-4, 1, 6), 60.),
+        keyframe(4., Point3::from(0, 5, -10), 45.),
+    ];
+    let mut path = CameraPath::new();
+    // Keyframes can be added in any order.
+    for i in [2, 0, 3, 1] {
+        path.add(keyframes[i]);
+    }
+
+    for k in keyframes {
+        let at = path.at(k.time).unwrap();
+        assert_close(at.lookfrom, k.lookfrom);
+        assert!((at.vfov - k.vfov).abs() < 1e-9, "{} != {}", at.vfov, k.vfov);
+    }
+
+    // The camera doesn't jump or turn sharply at the keyframes.
+    const DT: f64 = 1e-6;
+    for k in &keyframes[1..3] {
+        let before = path.at(k.time - DT).unwrap().lookfrom;
+        let after = path.at(k.time + DT).unwrap().lookfrom;
+        assert!((before - after).length() < 1e-4, "{before} != {after}");
+        let speed_before = (k.lookfrom - before) / DT;
+        let speed_after = (after - k.lookfrom) / DT;
+        assert!((speed_before - speed_after).length() < 1e-3);
+    }
+
+    // The camera holds still outside of the path.
+    assert_close(path.at(-1.).unwrap().lookfrom, keyframes[0].lookfrom);
+    assert_close(path.at(10.).unwrap().lookfrom, keyframes[3].lookfrom);
+}
+
+#[test]
+fn keyframes_along_a_line_give_a_steady_motion() {
+    let mut path = CameraPath::new();
+    let velocity = Vec3::from(1, -2, 0.5);
+    for time in [0., 0.5, 2., 3.] {
+        path.add(keyframe(time, time * velocity, 40.));
+    }
+    for step in 0..=30 {
+        let time = step as f64 / 10.;
+        assert_close(path.at(time).unwrap().lookfrom, time * velocity);
+    }
+}
+
+#[test]
+fn empty_paths_have_no_camera() {
+    assert!(CameraPath::new().at(0.).is_none());
+}
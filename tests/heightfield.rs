@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use raytracing::{
+    material::Lambertian, Color, Heightfield, HitRecord, Hittable, Interval, Material, Point3, Ray,
+    Vec3,
+};
+
+fn lambertian() -> Arc<dyn Material> {
+    Arc::new(Lambertian::new(Color::from(0.5, 0.5, 0.5)))
+}
+
+fn hit(heightfield: &Heightfield, r: &Ray) -> Option<HitRecord> {
+    let mut rec = HitRecord::default();
+    heightfield
+        .hit(r, Interval::from(0.001, f64::INFINITY), &mut rec)
+        .then_some(rec)
+}
+
+fn assert_close(a: Vec3, b: Vec3) {
+    assert!((a - b).near_zero(), "{a} != {b}");
+}
+
+/// Shoot a ray straight down onto the point `x`, `z`.
+fn down_at(x: f64, z: f64) -> Ray {
+    Ray::new(Point3::from(x, 5, z), Vec3::from(0, -1, 0))
+}
+
+#[test]
+fn flat_terrain_is_hit() {
+    let flat = Heightfield::new(
+        vec![0.5; 16],
+        4,
+        4,
+        Point3::new(),
+        Vec3::from(3, 2, 3),
+        lambertian(),
+    );
+    for r in [
+        down_at(1.2, 2.7),
+        Ray::new(Point3::from(-1, 3, -1), Vec3::from(1, -1, 1)),
+    ] {
+        let rec = hit(&flat, &r).expect("the ray should hit the flat terrain");
+        assert!((rec.p.y() - 1.).abs() < 1e-9, "{}", rec.p);
+        assert_close(rec.normal, Vec3::from(0, 1, 0));
+    }
+}
+
+#[test]
+fn sloped_terrain_is_hit_at_its_height() {
+    // Heights rise linearly along x, so the surface is the plane y = x / 2.
+    let heights = (0..3).flat_map(|_| [0., 0.5, 1.]).collect();
+    let slope = Heightfield::new(
+        heights,
+        3,
+        3,
+        Point3::new(),
+        Vec3::from(2, 1, 2),
+        lambertian(),
+    );
+    let rec = hit(&slope, &down_at(0.5, 0.7)).unwrap();
+    assert!((rec.t - 4.75).abs() < 1e-9, "{}", rec.t);
+    assert_close(rec.p, Point3::from(0.5, 0.25, 0.7));
+    assert_close(rec.normal, Vec3::from(-0.5, 1, 0).unit_vector());
+    assert_close(rec.geometric_normal, Vec3::from(-0.5, 1, 0).unit_vector());
+    assert!((rec.u - 0.25).abs() < 1e-9 && (rec.v - 0.35).abs() < 1e-9);
+}
+
+#[test]
+fn rays_passing_over_cells_miss() {
+    // A single peak in the far corner makes the bounding box tall, while the rest stays flat.
+    let mut heights = vec![0.; 16];
+    heights[15] = 1.;
+    let terrain = Heightfield::new(
+        heights,
+        4,
+        4,
+        Point3::new(),
+        Vec3::from(3, 1, 3),
+        lambertian(),
+    );
+    // Inside the bounding box, but above every cell it crosses.
+    let skimming = Ray::new(Point3::from(-1, 0.1, 0.5), Vec3::from(1, 0, 0));
+    assert!(hit(&terrain, &skimming).is_none());
+    // Climbing past the peak's cell on the side where the peak has not risen yet.
+    let climbing = Ray::new(Point3::from(-1, 0.05, 2.5), Vec3::from(1, 0.01, -0.3));
+    assert!(hit(&terrain, &climbing).is_none());
+    // Cell boundaries don't let rays through.
+    assert!(hit(&terrain, &down_at(1., 1.)).is_some());
+    assert!(hit(&terrain, &down_at(3.5, 1.)).is_none());
+}
+
+#[test]
+fn images_give_the_heights() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/ramp.pgm");
+    let ramp =
+        Heightfield::from_image(path, Point3::new(), Vec3::from(2, 1, 1), lambertian()).unwrap();
+    // The image brightens from left to right, so the terrain is the plane y = x / 2.
+    for (x, z) in [(0.2, 0.1), (1., 0.5), (1.7, 0.9)] {
+        let rec = hit(&ramp, &down_at(x, z)).unwrap();
+        assert_close(rec.p, Point3::from(x, x / 2., z));
+        assert_close(rec.normal, Vec3::from(-0.5, 1, 0).unit_vector());
+    }
+}
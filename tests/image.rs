@@ -0,0 +1,22 @@
+use std::io;
+
+use raytracing::Image;
+
+#[test]
+fn oversized_headers_are_rejected() {
+    let huge = usize::MAX / 2;
+    for header in [
+        format!("P6\n{huge} {huge}\n255\n"),
+        format!("P5\n{} 1\n65535\n", usize::MAX / 3),
+        format!("P2\n{huge} 3\n255\n0 0 0\n"),
+    ] {
+        let error = Image::parse(header.as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{header}");
+    }
+}
+
+#[test]
+fn truncated_raster_is_rejected() {
+    let error = Image::parse(b"P5\n100000 100000\n255\n\x00\x01").unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
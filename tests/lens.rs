@@ -0,0 +1,56 @@
+use std::{io, sync::Arc};
+
+use raytracing::{Camera, HittableList, LensElement, LensSystem, Projection};
+
+/// A biconvex lens of about 50mm focal length.
+fn singlet() -> LensSystem {
+    let element = |curvature_radius, thickness, ior| LensElement {
+        curvature_radius,
+        thickness,
+        ior,
+        aperture_radius: 10.,
+    };
+    LensSystem::new(vec![element(50., 5., 1.5), element(-50., 40., 1.)], 35.)
+}
+
+fn render(focus_dist: f64) -> io::Result<()> {
+    let mut camera = Camera::new();
+    camera.image_width = 4;
+    camera.samples_per_pixel = 1;
+    camera.projection = Projection::Lens(Arc::new(singlet()));
+    camera.focus_dist = focus_dist;
+    camera.render_to(&HittableList::new(), &mut Vec::new())
+}
+
+#[test]
+fn lens_focuses_far_away() {
+    assert!(singlet().focus(5.).is_some());
+    render(5.).unwrap();
+}
+
+#[test]
+fn focusing_closer_than_the_lens_allows_is_an_error() {
+    assert!(singlet().focus(0.01).is_none());
+    let error = render(0.01).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn double_gauss_prescription_is_a_50mm_lens() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/lenses/dgauss.50mm.dat");
+    let lens = LensSystem::load(path, 43.3).unwrap();
+    assert_eq!(lens.elements().len(), 11);
+
+    // Newton's lens equation: an object x before the front focal point is imaged x' behind
+    // the rear focal point, with x x' = f². Focusing moves the whole lens by x', which leaves
+    // two unknowns, the focal length and where the front focal point is at infinity focus.
+    let infinity = lens.focus(1000.).unwrap();
+    let [(a1, x1), (a2, x2)] = [0.5, 2.].map(|distance| {
+        let sensor_distance = lens.focus(distance).unwrap();
+        let extension = sensor_distance - infinity;
+        (distance * 1000. - extension, extension)
+    });
+    let front_focal_point = (a1 * x1 - a2 * x2) / (x1 - x2);
+    let focal_length = ((a1 - front_focal_point) * x1).sqrt();
+    assert!((focal_length - 50.).abs() < 1., "{focal_length}");
+}
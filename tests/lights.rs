@@ -0,0 +1,118 @@
+use raytracing::{
+    Color, DirectionalLight, Falloff, Light, LightSample, Point3, PointLight, SpotLight, Vec3,
+};
+
+fn assert_close(a: Vec3, b: Vec3) {
+    assert!((a - b).near_zero(), "{a} != {b}");
+}
+
+/// The light reaching a point `distance` below a light at `position`.
+fn sample_below(light: &dyn Light, position: Point3, distance: f64) -> Option<LightSample> {
+    light.sample(position - Vec3::from(0, distance, 0))
+}
+
+#[test]
+fn point_lights_follow_their_falloff() {
+    let position = Point3::from(1, 2, 3);
+    let intensity = Color::from(4, 8, 12);
+    let falloffs = [
+        (Falloff::Quadratic, 0.25),
+        (Falloff::Linear, 0.5),
+        (Falloff::Constant, 1.),
+    ];
+    for (falloff, attenuation) in falloffs {
+        let light = PointLight::with_falloff(position, intensity, falloff);
+        let sample = sample_below(&light, position, 2.).unwrap();
+        assert_close(sample.wi, Vec3::from(0, 1, 0));
+        assert!((sample.distance - 2.).abs() < 1e-12, "{}", sample.distance);
+        assert_close(sample.li, attenuation * intensity);
+    }
+
+    // Point lights are physically correct by default, and shine the same way everywhere.
+    let light = PointLight::new(position, intensity);
+    let p = position + Vec3::from(3, 0, -4);
+    let sample = light.sample(p).unwrap();
+    assert_close(sample.wi, Vec3::from(-0.6, 0, 0.8));
+    assert_close(sample.li, intensity / 25.);
+}
+
+#[test]
+fn spot_lights_fade_out_at_the_edge_of_their_cone() {
+    let position = Point3::from(0, 5, 0);
+    let intensity = Color::from(2, 2, 2);
+    // Pointing down, fully lit up to 20 degrees and dark past 30.
+    let light = SpotLight::new(position, Vec3::from(0, -3, 0), intensity, 30., 10.);
+
+    // Lit from below at `angle` degrees off the axis of the spot, at a distance of 2.
+    let at = |angle: f64| {
+        let angle = angle.to_radians();
+        light.sample(position + 2. * Vec3::from(angle.sin(), -angle.cos(), 0))
+    };
+
+    for angle in [0., 10., 19.9] {
+        let sample = at(angle).unwrap();
+        assert_close(sample.li, intensity / 4.);
+        assert!((sample.distance - 2.).abs() < 1e-12, "{}", sample.distance);
+    }
+    let edge: Vec<f64> = [21., 25., 29.]
+        .into_iter()
+        .map(|angle| at(angle).unwrap().li.r() / 0.5)
+        .collect();
+    assert!(edge.windows(2).all(|w| w[0] > w[1]), "{edge:?}");
+    assert!(edge[0] < 1. && edge[2] > 0., "{edge:?}");
+    assert!(at(30.1).is_none());
+    assert!(at(90.).is_none());
+    assert!(at(180.).is_none());
+
+    // The falloff applies within the cone as it does for point lights.
+    let light = SpotLight::with_falloff(
+        position,
+        Vec3::from(0, -1, 0),
+        intensity,
+        30.,
+        0.,
+        Falloff::Linear,
+    );
+    assert_close(
+        sample_below(&light, position, 4.).unwrap().li,
+        intensity / 4.,
+    );
+}
+
+#[test]
+fn directional_lights_reach_everywhere_from_the_same_direction() {
+    let irradiance = Color::from(3, 2, 1);
+    let light = DirectionalLight::new(Vec3::from(0, -2, 0), irradiance, 0.);
+    for p in [Point3::new(), Point3::from(100, -50, 7)] {
+        let sample = light.sample(p).unwrap();
+        assert_close(sample.wi, Vec3::from(0, 1, 0));
+        assert_eq!(sample.distance, f64::INFINITY);
+        assert_close(sample.li, irradiance);
+    }
+}
+
+#[test]
+fn wide_directional_lights_cover_a_disk_and_keep_their_irradiance() {
+    let irradiance = Color::from(3, 2, 1);
+    // A disk 20 degrees across, so 10 degrees around the direction toward the light.
+    let light = DirectionalLight::new(Vec3::from(0, -1, 0), irradiance, 20.);
+    let cos_max = 10_f64.to_radians().cos();
+
+    let n = 100_000;
+    let mut received = Color::new();
+    let mut widest: f64 = 1.;
+    for _ in 0..n {
+        let sample = light.sample(Point3::new()).unwrap();
+        let cos_theta = sample.wi.dot(Vec3::from(0, 1, 0));
+        assert!(cos_theta >= cos_max - 1e-12, "{cos_theta}");
+        assert!((sample.wi.length() - 1.).abs() < 1e-12);
+        widest = widest.min(cos_theta);
+        received += sample.li * cos_theta;
+    }
+    // The disk is filled to its edge.
+    assert!(widest < cos_max + 1e-4, "{widest}");
+    // A surface facing the light gets the irradiance, save for the slight tilt of the rays
+    // from the edge of the disk.
+    let received = received / n as f64 / ((1. + cos_max) / 2.);
+    assert!((received - irradiance).length() < 1e-3, "{received}");
+}
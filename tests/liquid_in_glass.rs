@@ -0,0 +1,68 @@
+use std::sync::Arc;
+
+use raytracing::{
+    material::{Dielectric, DiffuseLight, Ior},
+    Camera, Color, HittableList, Image, Medium, Point3, Sphere, Vec3,
+};
+
+/// Fraction of the red light absorbed by the liquid per unit of distance.
+const ABSORPTION: f64 = 0.5;
+
+/// Render a glass sphere filled with a liquid that absorbs red light, in front of a white wall
+/// of light, and return the linear color of the pixels.
+fn render() -> Image {
+    let mut world = HittableList::new();
+    let medium = |ior, absorption, priority| Medium {
+        absorption,
+        priority,
+        ..Medium::new(Ior::Constant(ior))
+    };
+    world.add(Arc::new(Sphere::new(
+        Point3::new(),
+        1,
+        Arc::new(Dielectric::with_medium(medium(1.5, Color::new(), 1))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(),
+        0.9,
+        Arc::new(Dielectric::with_medium(medium(
+            1.33,
+            Color::from(ABSORPTION, 0, 0),
+            2,
+        ))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::from(0, 0, -100),
+        90,
+        Arc::new(DiffuseLight::new(Color::from(1, 1, 1))),
+    )));
+
+    let mut cam = Camera::new();
+    cam.aspect_ratio = 1.;
+    cam.image_width = 9;
+    cam.samples_per_pixel = 500;
+    cam.max_depth = 50;
+    cam.vfov = 25.;
+    cam.lookfrom = Point3::from(0, 0, 5);
+    cam.lookat = Point3::new();
+    cam.vup = Vec3::from(0, 1, 0);
+
+    let mut ppm = Vec::new();
+    cam.render_to(&world, &mut ppm).unwrap();
+    Image::parse(&ppm).unwrap()
+}
+
+#[test]
+fn liquid_stays_inside_its_glass() {
+    let image = render();
+
+    // The wall around the glass is seen directly.
+    assert_eq!(image.pixel(0, 0), Color::from(1, 1, 1));
+
+    // Through the middle, light crosses 1.8 units of liquid. Paths that kept the liquid after
+    // leaving the glass would lose nearly all their red on the way to the wall.
+    let center = image.pixel(4, 4);
+    let ratio = (center.r() / center.g()).powi(2);
+    let expected = (-ABSORPTION * 1.8).exp();
+    assert!((ratio - expected).abs() < 0.05, "{ratio} != {expected}");
+}
@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use raytracing::{
+    material::Lambertian, random_float, Aabb, Color, HitRecord, Hittable, Interval, Material,
+    Point3, Ray, RotateY, Sphere, Translate, Vec3,
+};
+
+fn lambertian() -> Arc<dyn Material> {
+    Arc::new(Lambertian::new(Color::from(0.5, 0.5, 0.5)))
+}
+
+fn random_in(interval: Interval) -> f64 {
+    interval.min + random_float() * interval.size()
+}
+
+fn contains(bbox: Aabb, p: Point3) -> bool {
+    const EPSILON: f64 = 1e-9;
+    (0..3).all(|axis| bbox.axis(axis).expand(EPSILON).contains(p[axis]))
+}
+
+/// Shoot rays at `object` from all around, aimed at its bounding box, at times spread over the shutter interval, and check
+/// that every hit lies inside its bounding box.
+fn assert_hits_stay_in_the_box(object: &dyn Hittable) {
+    let bbox = object.bounding_box();
+    let mut hits = 0;
+    for step in 0..=20 {
+        let time = step as f64 / 20.;
+        for _ in 0..500 {
+            let origin = 10. * Vec3::random_unit_vector();
+            let target = Point3::from(
+                random_in(bbox.x.expand(1.)),
+                random_in(bbox.y.expand(1.)),
+                random_in(bbox.z.expand(1.)),
+            );
+            let r = Ray::with_time(origin, target - origin, time);
+            let mut rec = HitRecord::default();
+            if object.hit(&r, Interval::from(0.001, f64::INFINITY), &mut rec) {
+                assert!(contains(bbox, rec.p), "{} at time {time}", rec.p);
+                hits += 1;
+            }
+        }
+    }
+    assert!(hits > 500, "{hits}");
+}
+
+/// Whether a ray at `time` aimed at `point` from far away along z hits `object`.
+fn hits_at(object: &dyn Hittable, point: Point3, time: f64) -> bool {
+    let r = Ray::with_time(point + Vec3::from(0, 0, 10), Vec3::from(0, 0, -1), time);
+    object.hit(
+        &r,
+        Interval::from(0.001, f64::INFINITY),
+        &mut HitRecord::default(),
+    )
+}
+
+#[test]
+fn moving_spheres_are_bounded_at_both_ends_of_their_path() {
+    let sphere = Sphere::new_moving(
+        Point3::from(-1, 0, 0),
+        Point3::from(2, 1, 0),
+        0.5,
+        lambertian(),
+    );
+    let bbox = sphere.bounding_box();
+    for (axis, min, max) in [(0, -1.5, 2.5), (1, -0.5, 1.5), (2, -0.5, 0.5)] {
+        assert!((bbox.axis(axis).min - min).abs() < 1e-12, "{axis}");
+        assert!((bbox.axis(axis).max - max).abs() < 1e-12, "{axis}");
+    }
+    assert_hits_stay_in_the_box(&sphere);
+
+    // The sphere is where its path puts it at the time of the ray.
+    assert!(hits_at(&sphere, Point3::from(-1, 0, 0), 0.));
+    assert!(!hits_at(&sphere, Point3::from(-1, 0, 0), 1.));
+    assert!(hits_at(&sphere, Point3::from(0.5, 0.5, 0), 0.5));
+    assert!(hits_at(&sphere, Point3::from(2, 1, 0), 1.));
+}
+
+#[test]
+fn moving_transforms_are_bounded_over_the_shutter_interval() {
+    let ball = || -> Arc<dyn Hittable> {
+        Arc::new(Sphere::new(Point3::from(1.5, 0, 0), 0.5, lambertian()))
+    };
+    let translated = Translate::new_moving(ball(), Vec3::new(), Vec3::from(-1, 1, 0));
+    assert_hits_stay_in_the_box(&translated);
+    assert!(hits_at(&translated, Point3::from(1.5, 0, 0), 0.));
+    assert!(hits_at(&translated, Point3::from(0.5, 1, 0), 1.));
+
+    // Halfway through a half turn, the ball is on the z axis, out of the boxes of both ends.
+    let spinning = RotateY::new_moving(ball(), 0., 180.);
+    assert_hits_stay_in_the_box(&spinning);
+    let r = Ray::with_time(Point3::from(0, 0, 10), Vec3::from(0, 0, -1), 0.5);
+    let mut rec = HitRecord::default();
+    assert!(spinning.hit(&r, Interval::from(0.001, f64::INFINITY), &mut rec));
+    assert!(contains(spinning.bounding_box(), rec.p), "{}", rec.p);
+}
@@ -0,0 +1,208 @@
+use std::sync::Arc;
+
+use raytracing::{
+    material::{Dielectric, DiffuseLight, Ior, Lambertian, RoughDielectric},
+    Color, HitRecord, Hittable, HittableList, Interval, Light, Material, Medium, Point3,
+    PointLight, Ray, Scene, Sphere, Vec3,
+};
+
+const GLASS: f64 = 1.5;
+const WATER: f64 = 1.33;
+
+/// A sphere of glass filled with water: the water is the inner sphere, with a higher priority
+/// so that it replaces the glass inside of it.
+fn glass_of_water(glass_priority: u32, water_priority: u32) -> HittableList {
+    let medium = |ior, priority| {
+        Arc::new(Dielectric::with_medium(Medium {
+            priority,
+            ..Medium::new(Ior::Constant(ior))
+        }))
+    };
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(),
+        1,
+        medium(GLASS, glass_priority),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(),
+        0.9,
+        medium(WATER, water_priority),
+    )));
+    world
+}
+
+/// Follow a ray through `world`, always refracting, and return the ratio of the sines of the
+/// refracted and incident angles at each surface, i.e. the relative index of refraction.
+fn refraction_ratios(world: &HittableList) -> Vec<f64> {
+    let mut ray = Ray::new(Point3::from(-5, 0.3, 0.1), Vec3::from(1, 0, 0));
+    let mut ratios = Vec::new();
+    let mut rec = HitRecord::default();
+    while world.hit(&ray, Interval::from(0.001, f64::INFINITY), &mut rec) {
+        // Resample until the light crosses the surface instead of reflecting.
+        let scattered = loop {
+            let Some(sample) = rec.material.sample(&ray, &rec) else {
+                continue;
+            };
+            if sample.scattered.direction().dot(rec.normal) < 0. {
+                break sample.scattered;
+            }
+        };
+        let sin = |d: Vec3| d.unit_vector().cross(rec.normal).length();
+        ratios.push(sin(scattered.direction()) / sin(ray.direction()));
+        ray = scattered;
+    }
+    assert!(ray.media().is_empty());
+    ratios
+}
+
+fn assert_ratios(actual: &[f64], expected: &[f64]) {
+    assert_eq!(actual.len(), expected.len(), "{actual:?}");
+    for (a, e) in actual.iter().zip(expected) {
+        assert!((a - e).abs() < 1e-9, "{actual:?} != {expected:?}");
+    }
+}
+
+#[test]
+fn liquid_in_a_glass_refracts_between_glass_and_liquid() {
+    let ratios = refraction_ratios(&glass_of_water(1, 2));
+    assert_ratios(
+        &ratios,
+        &[1. / GLASS, GLASS / WATER, WATER / GLASS, GLASS / 1.],
+    );
+}
+
+#[test]
+fn surfaces_inside_a_higher_priority_medium_are_ignored() {
+    // The glass now fills the inside of the water sphere too.
+    let ratios = refraction_ratios(&glass_of_water(2, 1));
+    assert_ratios(&ratios, &[1. / GLASS, 1., 1., GLASS / 1.]);
+}
+
+#[test]
+fn rough_dielectric_refracts_against_the_surrounding_medium() {
+    // A frosted sphere in a liquid of the same index of refraction disappears: light goes
+    // straight through it.
+    let liquid = |priority| Medium {
+        priority,
+        ..Medium::new(Ior::Constant(WATER))
+    };
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(),
+        1,
+        Arc::new(Dielectric::with_medium(liquid(1))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(),
+        0.5,
+        Arc::new(RoughDielectric::with_medium(liquid(2), 0.5)),
+    )));
+    let ratios = refraction_ratios(&world);
+    assert_ratios(&ratios, &[1. / WATER, 1., 1., WATER / 1.]);
+}
+
+#[test]
+fn rough_dielectric_leaves_no_medium_behind() {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(),
+        1,
+        Arc::new(RoughDielectric::new(GLASS, 0.3)),
+    )));
+    // The ray leaves the sphere with an empty medium stack, which `refraction_ratios` checks.
+    assert_eq!(refraction_ratios(&world).len(), 2);
+}
+
+#[test]
+fn absorption_depends_on_the_current_medium() {
+    let absorbing = Dielectric::with_medium(Medium {
+        absorption: Vec3::from(1, 0, 0),
+        ..Medium::new(Ior::Constant(GLASS))
+    });
+    let rec = HitRecord {
+        front_face: true,
+        normal: Vec3::from(0, 0, 1),
+        ..Default::default()
+    };
+    let ray = Ray::new(Point3::from(0, 0, 1), Vec3::from(0, 0, -1));
+
+    // Entering at normal incidence either reflects, staying in air, or enters the medium.
+    let sample = loop {
+        let sample = absorbing.sample(&ray, &rec).unwrap();
+        if sample.scattered.direction().z() < 0. {
+            break sample;
+        }
+    };
+    let transmittance = sample.scattered.media().transmittance(2.);
+    assert!((transmittance.x() - (-2f64).exp()).abs() < 1e-12);
+    assert_eq!(transmittance.y(), 1.);
+    assert_eq!(
+        ray.media().transmittance(f64::INFINITY),
+        Vec3::from(1, 1, 1)
+    );
+}
+
+/// The direct light reaching a white ball at the bottom of a pool of water that absorbs red
+/// light only, along with the distance from the lit point to `light`.
+fn direct_light_under_water(
+    world: &mut HittableList,
+    lights: &[Arc<dyn Light>],
+    light: Point3,
+) -> Vec<(Color, f64)> {
+    let water = Medium {
+        absorption: Color::from(0.2, 0, 0),
+        ..Medium::new(Ior::Constant(WATER))
+    };
+    world.add(Arc::new(Sphere::new(
+        Point3::new(),
+        10,
+        Arc::new(Dielectric::with_medium(water)),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::from(0, -2, 0),
+        1,
+        Arc::new(Lambertian::new(Color::from(1, 1, 1))),
+    )));
+    let scene = Scene::new(world, lights);
+
+    // Dive into the water, then look at the top of the ball.
+    let ray = Ray::new(Point3::from(0.6, 20, 0), Vec3::from(0, -1, 0));
+    let mut rec = HitRecord::default();
+    assert!(scene.hit(&ray, &mut rec));
+    let ray = loop {
+        let Some(sample) = rec.material.sample(&ray, &rec) else {
+            continue;
+        };
+        if sample.scattered.direction().dot(rec.normal) < 0. {
+            break sample.scattered;
+        }
+    };
+    assert!(scene.hit(&ray, &mut rec));
+    (0..1000)
+        .map(|_| (scene.direct_light(&ray, &rec), (light - rec.p).length()))
+        .collect()
+}
+
+#[test]
+fn shadow_rays_are_absorbed_by_the_medium() {
+    let light = Point3::from(0, 2, 0);
+    let lights: [Arc<dyn Light>; 1] = [Arc::new(PointLight::new(light, Color::from(1, 1, 1)))];
+    for (color, distance) in direct_light_under_water(&mut HittableList::new(), &lights, light) {
+        assert!((color.x() / color.y() - (-0.2 * distance).exp()).abs() < 1e-9);
+    }
+
+    // Shadow rays toward a small lamp travel between the nearest and farthest points of it.
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        light,
+        0.25,
+        Arc::new(DiffuseLight::new(Color::from(1, 1, 1))),
+    )));
+    for (color, distance) in direct_light_under_water(&mut world, &[], light) {
+        assert!(color.y() > 0.);
+        let ratio = color.x() / color.y();
+        assert!(ratio > (-0.2 * distance).exp() - 1e-9);
+        assert!(ratio < (-0.2 * (distance - 0.25)).exp() + 1e-9);
+    }
+}
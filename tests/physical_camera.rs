@@ -0,0 +1,64 @@
+use std::sync::{Arc, Mutex};
+
+use raytracing::{
+    Camera, Color, HittableList, Integrator, PhysicalCamera, Point3, Ray, Scene, Vec3,
+};
+
+/// Records the time of every camera ray.
+struct RayTimes(Arc<Mutex<Vec<f64>>>);
+
+impl Integrator for RayTimes {
+    fn li(&self, r: &Ray, _scene: &Scene, _max_depth: usize) -> Color {
+        self.0.lock().unwrap().push(r.time());
+        Color::new()
+    }
+}
+
+/// The range of the times of the rays traced by `cam`.
+fn shutter(mut cam: Camera) -> (f64, f64) {
+    let times = Arc::new(Mutex::new(Vec::new()));
+    cam.image_width = 8;
+    cam.samples_per_pixel = 50;
+    cam.lookfrom = Point3::from(0, 0, 5);
+    cam.lookat = Point3::new();
+    cam.vup = Vec3::from(0, 1, 0);
+    cam.integrator = Box::new(RayTimes(times.clone()));
+    cam.render_to(&HittableList::new(), &mut Vec::new())
+        .unwrap();
+
+    let times = times.lock().unwrap();
+    let min = times.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = times.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    (min, max)
+}
+
+#[test]
+fn shutter_time_is_measured_in_frames() {
+    let physical = PhysicalCamera {
+        shutter_time: 1. / 100.,
+        frame_duration: 1. / 25.,
+        ..PhysicalCamera::new()
+    };
+    assert!((physical.shutter_fraction() - 0.25).abs() < 1e-12);
+
+    let mut cam = Camera::new();
+    cam.shutter_open = 0.5;
+    cam.physical = Some(physical);
+    let (open, close) = shutter(cam);
+    assert!(open >= 0.5 && close <= 0.75, "{open}..{close}");
+    // With hundreds of rays, some are traced near each end of the shutter interval.
+    assert!(open < 0.51 && close > 0.74, "{open}..{close}");
+}
+
+#[test]
+fn long_exposures_stop_at_the_end_of_the_frame() {
+    let mut cam = Camera::new();
+    cam.shutter_open = 0.5;
+    cam.physical = Some(PhysicalCamera {
+        shutter_time: 1.,
+        ..PhysicalCamera::new()
+    });
+    let (open, close) = shutter(cam);
+    assert!(open >= 0.5 && close <= 1., "{open}..{close}");
+    assert!(close > 0.99, "{close}");
+}
@@ -0,0 +1,279 @@
+use std::f64::consts::PI;
+
+use raytracing::{
+    Camera, Color, FisheyeMapping, HittableList, Image, Integrator, Point3, Projection, Ray, Scene,
+    Stereo, StereoLayout, Vec3,
+};
+
+/// Colors every camera ray with a vector computed from it, whose coordinates must lie in
+/// [-1, 1].
+struct Encode(fn(&Ray) -> Vec3);
+
+impl Integrator for Encode {
+    fn li(&self, r: &Ray, _scene: &Scene, _max_depth: usize) -> Color {
+        ((self.0)(r) + Color::from(1, 1, 1)) / 2.
+    }
+}
+
+/// Render an empty scene through `cam`, with the pixels colored by `encode`, and return the
+/// average vector each pixel saw. Pixels without any ray come out as (-1, -1, -1).
+fn render(mut cam: Camera, encode: fn(&Ray) -> Vec3) -> impl Fn(usize, usize) -> Vec3 {
+    cam.samples_per_pixel = 16;
+    cam.integrator = Box::new(Encode(encode));
+    let mut ppm = Vec::new();
+    cam.render_to(&HittableList::new(), &mut ppm).unwrap();
+    let image = Image::parse(&ppm).unwrap();
+    move |x, y| {
+        let color = image.pixel(x, y);
+        2. * color * color - Color::from(1, 1, 1)
+    }
+}
+
+fn direction(r: &Ray) -> Vec3 {
+    r.direction().unit_vector()
+}
+
+/// A camera at `lookfrom` looking at the origin, with y up.
+fn camera(lookfrom: Point3, projection: Projection) -> Camera {
+    let mut cam = Camera::new();
+    cam.lookfrom = lookfrom;
+    cam.lookat = Point3::new();
+    cam.vup = Vec3::from(0, 1, 0);
+    cam.projection = projection;
+    cam
+}
+
+/// Compare vectors read back from 8 bit colors, averaged over the area of a pixel.
+fn assert_close(a: Vec3, b: Vec3) {
+    assert!((a - b).length() < 0.1, "{a} != {b}");
+}
+
+#[test]
+fn orthographic_rays_are_parallel_and_start_across_the_view() {
+    let cam = || {
+        let mut cam = camera(
+            Point3::from(0, 0, 5),
+            Projection::Orthographic { view_width: 4. },
+        );
+        cam.aspect_ratio = 2.;
+        cam.image_width = 40;
+        cam
+    };
+
+    let pixel = render(cam(), direction);
+    for (x, y) in [(0, 0), (20, 10), (39, 19), (5, 17)] {
+        assert_close(pixel(x, y), Vec3::from(0, 0, -1));
+    }
+
+    // Origins are scaled down to fit the colors.
+    let pixel = render(cam(), |r| r.origin() / 2. - Vec3::from(0, 0, 2.5));
+    for (x, y) in [(0, 0), (20, 10), (39, 19), (5, 17)] {
+        let expected = Point3::from(-1.95 + 0.1 * x as f64, 0.95 - 0.1 * y as f64, 5);
+        assert_close(2. * pixel(x, y) + Vec3::from(0, 0, 5), expected);
+    }
+}
+
+#[test]
+fn equirectangular_pixels_follow_longitude_and_latitude() {
+    let mut cam = camera(Point3::from(0, 0, 5), Projection::Equirectangular);
+    cam.image_width = 40;
+    let pixel = render(cam, direction);
+
+    for (x, y) in [
+        (20, 10),
+        (10, 10),
+        (30, 10),
+        (0, 10),
+        (20, 0),
+        (20, 19),
+        (7, 4),
+    ] {
+        // Longitude from the front of the camera, and latitude from the horizon.
+        let phi = ((x as f64 + 0.5) / 40. - 0.5) * 2. * PI;
+        let theta = (0.5 - (y as f64 + 0.5) / 20.) * PI;
+        let expected = Vec3::from(
+            theta.cos() * phi.sin(),
+            theta.sin(),
+            -theta.cos() * phi.cos(),
+        );
+        assert_close(pixel(x, y), expected);
+    }
+    // The middle of the image is in front, and a quarter of the way in from each side.
+    assert!(pixel(20, 10).z() < -0.98);
+    assert!(pixel(10, 10).x() < -0.98);
+    assert!(pixel(30, 10).x() > 0.98);
+    assert!(pixel(20, 0).y() > 0.98);
+}
+
+#[test]
+fn fisheye_angles_grow_from_the_center_of_the_image() {
+    for mapping in [FisheyeMapping::Equidistant, FisheyeMapping::Equisolid] {
+        // Angle from the view direction at a distance `r` from the center, for a 180 degrees
+        // field of view.
+        let angle = |r: f64| match mapping {
+            FisheyeMapping::Equidistant => r * PI / 2.,
+            FisheyeMapping::Equisolid => 2. * (r * (PI / 4.).sin()).asin(),
+        };
+        let mut cam = camera(
+            Point3::from(0, 0, 5),
+            Projection::Fisheye { fov: 180., mapping },
+        );
+        cam.aspect_ratio = 1.;
+        cam.image_width = 40;
+        let pixel = render(cam, direction);
+
+        for (x, y) in [(20, 20), (30, 20), (20, 10), (8, 30), (33, 7)] {
+            // Position relative to the center, in units of the image circle radius.
+            let (s, t) = ((x as f64 + 0.5) / 20. - 1., 1. - (y as f64 + 0.5) / 20.);
+            let (theta, phi) = (angle(s.hypot(t)), t.atan2(s));
+            let expected = Vec3::from(
+                theta.sin() * phi.cos(),
+                theta.sin() * phi.sin(),
+                -theta.cos(),
+            );
+            assert_close(pixel(x, y), expected);
+        }
+        // Near the edge of the circle, the rays are almost perpendicular to the view.
+        assert!(pixel(38, 20).x() > 0.98);
+        // Corners are outside of the circle, and see nothing.
+        assert_eq!(pixel(0, 0), Vec3::from(-1, -1, -1));
+    }
+}
+
+#[test]
+fn cubemap_faces_look_along_the_camera_axes() {
+    let mut cam = camera(Point3::from(0, 0, 5), Projection::Cubemap);
+    cam.image_width = 60;
+    let pixel = render(cam, direction);
+
+    // The axis, right and up directions of each face, in layout order.
+    let x = Vec3::from(1, 0, 0);
+    let y = Vec3::from(0, 1, 0);
+    let z = Vec3::from(0, 0, 1);
+    let faces = [
+        (x, z, y),
+        (-x, -z, y),
+        (y, -x, -z),
+        (-y, -x, z),
+        (z, -x, y),
+        (-z, x, y),
+    ];
+    for (face, (axis, right, up)) in faces.into_iter().enumerate() {
+        let (left, top) = (20 * (face % 3), 20 * (face / 3));
+        for (x, y) in [(10, 10), (15, 10), (10, 4), (1, 18), (19, 0)] {
+            // Coordinates on the face, from -1 to 1 going right and down.
+            let (a, b) = ((x as f64 + 0.5) / 10. - 1., (y as f64 + 0.5) / 10. - 1.);
+            let expected = (axis + a * right - b * up).unit_vector();
+            assert_close(pixel(left + x, top + y), expected);
+        }
+    }
+}
+
+#[test]
+fn cubemap_front_face_matches_the_camera_view() {
+    let mut front = camera(Point3::from(0, 0, 5), Projection::Cubemap);
+    front.image_width = 60;
+    let front = render(front, direction);
+    let mut perspective = camera(Point3::from(0, 0, 5), Projection::Perspective);
+    perspective.aspect_ratio = 1.;
+    perspective.image_width = 20;
+    perspective.vfov = 90.;
+    let perspective = render(perspective, direction);
+
+    for (x, y) in [(2, 3), (17, 5), (10, 10), (4, 18), (19, 19)] {
+        assert_close(front(40 + x, 20 + y), perspective(x, y));
+    }
+}
+
+/// Where `r` leaves the sphere of radius `radius` around `center`, scaled down to a unit vector.
+fn exit_point(r: &Ray, center: Point3, radius: f64) -> Vec3 {
+    let d = r.direction().unit_vector();
+    let oc = r.origin() - center;
+    let half_b = oc.dot(d);
+    let t = -half_b + (half_b * half_b - oc.length_squared() + radius * radius).sqrt();
+    (r.origin() + t * d - center) / radius
+}
+
+#[test]
+fn stereo_eyes_sit_apart_and_converge() {
+    let lookfrom = Point3::from(0, 0, 5);
+    let cam = |layout, convergence| {
+        let mut cam = camera(lookfrom, Projection::Perspective);
+        cam.aspect_ratio = 1.;
+        cam.image_width = 20;
+        cam.focus_dist = 5.;
+        cam.stereo = Some(Stereo {
+            interocular: 1.,
+            convergence,
+            layout,
+        });
+        cam
+    };
+
+    // The eyes are half the interocular distance to each side of the camera, and parallel eyes
+    // see along the same directions.
+    let origin = render(cam(StereoLayout::SideBySide, f64::INFINITY), |r| {
+        r.origin() - Point3::from(0, 0, 5)
+    });
+    let parallel = render(cam(StereoLayout::SideBySide, f64::INFINITY), direction);
+    for (x, y) in [(10, 10), (3, 17)] {
+        assert_close(origin(x, y), Vec3::from(-0.5, 0, 0));
+        assert_close(origin(20 + x, y), Vec3::from(0.5, 0, 0));
+        assert_close(parallel(x, y), parallel(20 + x, y));
+    }
+
+    // Converging eyes see the same points at the convergence distance. The right eye comes
+    // below the left one when they are stacked.
+    let converged = render(cam(StereoLayout::TopBottom, 5.), |r| {
+        let t = -r.origin().z() / r.direction().z();
+        r.at(t) / 5.
+    });
+    for (x, y) in [(10, 10), (3, 17), (18, 1)] {
+        assert_close(converged(x, y), converged(x, 20 + y));
+    }
+}
+
+#[test]
+fn omnidirectional_stereo_eyes_circle_the_camera() {
+    let lookfrom = Point3::from(0, 0, 5);
+    let cam = |convergence| {
+        let mut cam = camera(lookfrom, Projection::Equirectangular);
+        cam.image_width = 40;
+        cam.stereo = Some(Stereo {
+            interocular: 1.,
+            convergence,
+            layout: StereoLayout::TopBottom,
+        });
+        cam
+    };
+
+    // Every column is seen by eyes turned toward it: looking ahead the left eye is on the left,
+    // and looking left it is behind the camera.
+    let origin = render(cam(f64::INFINITY), |r| r.origin() - Point3::from(0, 0, 5));
+    for (x, left_eye) in [(20, Vec3::from(-0.5, 0, 0)), (10, Vec3::from(0, 0, 0.5))] {
+        for y in [5, 10, 14] {
+            // Columns are a bit off the axes, so the eyes are too.
+            assert!((origin(x, y) - left_eye).length() < 0.1, "{}", origin(x, y));
+            assert!(
+                (origin(x, 20 + y) + left_eye).length() < 0.1,
+                "{}",
+                origin(x, 20 + y)
+            );
+        }
+    }
+
+    // Converging eyes both see the point of the mono panorama at the convergence distance.
+    let mono = render(
+        {
+            let mut cam = camera(lookfrom, Projection::Equirectangular);
+            cam.image_width = 40;
+            cam
+        },
+        direction,
+    );
+    let converged = render(cam(10.), |r| exit_point(r, Point3::from(0, 0, 5), 10.));
+    for (x, y) in [(20, 10), (10, 10), (33, 6), (5, 13)] {
+        assert_close(converged(x, y), mono(x, y));
+        assert_close(converged(x, 20 + y), mono(x, y));
+    }
+}
@@ -0,0 +1,54 @@
+use std::sync::Arc;
+
+use raytracing::{
+    material::{DiffuseLight, Lambertian},
+    Color, HittableList, Integrator, PathTracer, Point3, Ray, Scene, Sphere, Vec3,
+};
+
+const SAMPLES: usize = 20_000;
+const MAX_DEPTH: usize = 200;
+
+/// Mean and standard error of the brightness of the light seen along `r`.
+fn estimate(integrator: &impl Integrator, scene: &Scene, r: &Ray) -> (f64, f64) {
+    let mut sum = 0.;
+    let mut sum_squares = 0.;
+    for _ in 0..SAMPLES {
+        let color = integrator.li(r, scene, MAX_DEPTH);
+        let brightness = (color.x() + color.y() + color.z()) / 3.;
+        sum += brightness;
+        sum_squares += brightness * brightness;
+    }
+    let n = SAMPLES as f64;
+    let mean = sum / n;
+    let variance = (sum_squares / n - mean * mean).max(0.);
+    (mean, (variance / n).sqrt())
+}
+
+#[test]
+fn russian_roulette_preserves_mean_brightness() {
+    // A lamp inside a closed, bright room, so that paths keep bouncing for a long time.
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::from(0, 0, 0),
+        5,
+        Arc::new(Lambertian::new(Color::from(0.9, 0.8, 0.7))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::from(0, 0, 0),
+        1,
+        Arc::new(DiffuseLight::new(Color::from(4, 4, 4))),
+    )));
+    let scene = Scene::new(&world, &[]);
+    let r = Ray::new(Point3::from(0, 0, -3), Vec3::from(0.3, 0.2, -1));
+
+    let (reference, reference_error) = estimate(&PathTracer::new(MAX_DEPTH), &scene, &r);
+    let (roulette, roulette_error) = estimate(&PathTracer::new(1), &scene, &r);
+
+    // Unbiased estimates land within six standard errors of each other except once in
+    // hundreds of millions of runs.
+    let tolerance = 6. * (reference_error.powi(2) + roulette_error.powi(2)).sqrt();
+    assert!(
+        (reference - roulette).abs() < tolerance,
+        "{reference} vs {roulette}, tolerance {tolerance}"
+    );
+}
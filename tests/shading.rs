@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+use raytracing::{
+    material::{BumpMap, Lambertian, NormalMap},
+    texture::SolidColor,
+    Color, HitRecord, HittableList, Material, Point3, Ray, Scene, Sphere, Vec3,
+};
+
+/// Hit a unit sphere made of `material` at the origin, seen from `direction`.
+fn hit_sphere(material: Arc<dyn Material>, direction: Vec3) -> HitRecord {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(Point3::new(), 1, material)));
+    let scene = Scene::new(&world, &[]);
+    let direction = direction.unit_vector();
+    let mut rec = HitRecord::default();
+    assert!(scene.hit(&Ray::new(5. * direction, -direction), &mut rec));
+    rec
+}
+
+fn lambertian() -> Arc<dyn Material> {
+    Arc::new(Lambertian::new(Color::from(0.5, 0.5, 0.5)))
+}
+
+#[test]
+fn flat_maps_keep_the_geometric_normal() {
+    let flat_normals = Arc::new(SolidColor::new(Color::from(0.5, 0.5, 1)));
+    let no_bumps = Arc::new(SolidColor::gray(0.));
+    let materials: [Arc<dyn Material>; 2] = [
+        Arc::new(NormalMap::new(lambertian(), flat_normals)),
+        Arc::new(BumpMap::new(lambertian(), no_bumps, 0.1)),
+    ];
+    for material in materials {
+        let rec = hit_sphere(material, Vec3::from(0.3, 0.4, 1));
+        assert!(
+            (rec.normal - rec.geometric_normal).near_zero(),
+            "{} != {}",
+            rec.normal,
+            rec.geometric_normal
+        );
+    }
+}
+
+#[test]
+fn normal_maps_tilt_the_normal_used_by_integrators() {
+    // The map's red axis follows `dpdu`, so this normal lies flat along the surface.
+    let along_u = Arc::new(SolidColor::new(Color::from(1, 0.5, 0.5)));
+    let rec = hit_sphere(
+        Arc::new(NormalMap::new(lambertian(), along_u)),
+        Vec3::from(0.3, 0.4, 1),
+    );
+    assert!(rec.normal.dot(rec.geometric_normal).abs() < 1e-9);
+    assert!((rec.normal.length() - 1.).abs() < 1e-9);
+}
+
+#[test]
+fn sphere_tangents_follow_the_texture_coordinates() {
+    const DELTA: f64 = 1e-6;
+    for direction in [
+        Vec3::from(0.3, 0.4, 1),
+        Vec3::from(-1, 0.2, 0.1),
+        Vec3::from(0.5, -0.8, -0.3),
+    ] {
+        let rec = hit_sphere(lambertian(), direction);
+        assert!(rec.dpdu.dot(rec.normal).abs() < 1e-9);
+        assert!(rec.dpdv.dot(rec.normal).abs() < 1e-9);
+
+        // Stepping along a tangent moves the texture coordinates along its axis only.
+        for (tangent, du, dv) in [(rec.dpdu, 1., 0.), (rec.dpdv, 0., 1.)] {
+            let moved = hit_sphere(lambertian(), rec.p + DELTA * tangent);
+            assert!(((moved.u - rec.u) / DELTA - du).abs() < 1e-4, "{}", moved.u);
+            assert!(((moved.v - rec.v) / DELTA - dv).abs() < 1e-4, "{}", moved.v);
+        }
+    }
+}
+
+#[test]
+fn directions_across_the_geometry_but_not_the_shading_normal_disagree() {
+    let rec = HitRecord {
+        normal: Vec3::from(1, 0, 1).unit_vector(),
+        geometric_normal: Vec3::from(0, 0, 1),
+        front_face: true,
+        ..Default::default()
+    };
+    let wo = Vec3::from(0, 0, 1);
+    // Above the shading normal, yet below the surface.
+    let leaking = Vec3::from(1, 0, -0.2);
+    assert!(!rec.sides_agree(wo, leaking));
+    assert!(rec.sides_agree(wo, Vec3::from(1, 0, 0.2)));
+    assert!(rec.sides_agree(wo, Vec3::from(-1, 0, -0.2)));
+}
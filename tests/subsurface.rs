@@ -0,0 +1,41 @@
+use std::sync::Arc;
+
+use raytracing::{
+    material::{Dielectric, DiffuseLight, Ior},
+    Color, HittableList, Integrator, Medium, PathTracer, Point3, Ray, Scene, Sphere, Vec3,
+};
+
+#[test]
+fn non_absorbing_subsurface_conserves_energy() {
+    // A white translucent ball inside a furnace: a sphere glowing evenly toward its inside,
+    // which a negative radius turns inside out.
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(),
+        -10,
+        Arc::new(DiffuseLight::new(Color::from(1, 1, 1))),
+    )));
+    let medium = Medium::subsurface(
+        Ior::Constant(1.),
+        Color::from(1, 1, 1),
+        Color::from(0.1, 0.1, 0.1),
+    );
+    world.add(Arc::new(Sphere::new(
+        Point3::new(),
+        1,
+        Arc::new(Dielectric::with_medium(medium)),
+    )));
+    let scene = Scene::new(&world, &[]);
+
+    // Entering the ball, leaving it and reaching the furnace take three bounces, plus the rare
+    // reflections inside of it, however many times the light scatters in between.
+    let integrator = PathTracer::default();
+    let r = Ray::new(Point3::from(0, 0, 5), Vec3::from(0.02, 0.05, -1));
+    let samples = 10_000;
+    let mut sum = Color::new();
+    for _ in 0..samples {
+        sum += integrator.li(&r, &scene, 50);
+    }
+    let mean = sum / samples as f64;
+    assert!((mean.x() - 1.).abs() < 0.01, "{mean}");
+}
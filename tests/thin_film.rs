@@ -0,0 +1,104 @@
+use raytracing::{
+    fresnel,
+    material::{Metal, ThinFilm},
+    spectrum::HeroWavelengths,
+    Color, Complex, HitRecord, Material, Point3, Ray, Vec3,
+};
+
+const COSINES: [f64; 5] = [1., 0.8, 0.5, 0.2, 0.05];
+const WAVELENGTHS: [f64; 3] = [450., 550., 650.];
+
+/// Gold, roughly, at the middle of the visible spectrum.
+const GOLD: Complex = Complex::new(0.27, 2.78);
+
+fn assert_close(a: f64, b: f64) {
+    assert!((a - b).abs() < 1e-9, "{a} != {b}");
+}
+
+#[test]
+fn film_without_thickness_leaves_the_substrate_bare() {
+    for cos_theta in COSINES {
+        for wavelength in WAVELENGTHS {
+            for (eta_i, eta_t) in [(1., 1.5), (1.33, 1.5), (1.5, 1.33)] {
+                assert_close(
+                    fresnel::thin_film(cos_theta, eta_i, 1.8, Complex::from(eta_t), 0., wavelength),
+                    fresnel::dielectric(cos_theta, eta_t / eta_i),
+                );
+            }
+            assert_close(
+                fresnel::thin_film(cos_theta, 1., 1.8, GOLD, 0., wavelength),
+                fresnel::conductor(cos_theta, GOLD),
+            );
+        }
+    }
+}
+
+#[test]
+fn film_matching_the_outside_leaves_the_substrate_bare() {
+    for cos_theta in COSINES {
+        for wavelength in WAVELENGTHS {
+            for thickness in [100., 350., 800.] {
+                assert_close(
+                    fresnel::thin_film(
+                        cos_theta,
+                        1.33,
+                        1.33,
+                        Complex::from(1.5),
+                        thickness,
+                        wavelength,
+                    ),
+                    fresnel::dielectric(cos_theta, 1.5 / 1.33),
+                );
+                assert_close(
+                    fresnel::thin_film(cos_theta, 1., 1., GOLD, thickness, wavelength),
+                    fresnel::conductor(cos_theta, GOLD),
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn film_reflectance_is_a_fraction() {
+    for cos_theta in COSINES {
+        for wavelength in (380..=780).step_by(20) {
+            for thickness in (0..=1000).step_by(50) {
+                for eta_film in [1., 1.33, 1.8, 2.5] {
+                    for eta_t in [Complex::from(1.), Complex::from(1.5), GOLD] {
+                        let r = fresnel::thin_film(
+                            cos_theta,
+                            1.,
+                            eta_film,
+                            eta_t,
+                            thickness as f64,
+                            wavelength as f64,
+                        );
+                        assert!((0. ..=1.).contains(&r), "{r}");
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn spectral_paths_see_the_film_at_each_wavelength() {
+    let metal = Metal::new(Color::from(0.9, 0.9, 0.9), 0.).with_thin_film(ThinFilm::new(1.4, 300.));
+    let rec = HitRecord {
+        normal: Vec3::from(0, 0, 1),
+        front_face: true,
+        ..Default::default()
+    };
+    let hero = HeroWavelengths::new(480.);
+    let r_in = Ray::new(Point3::from(0, 0, 1), Vec3::from(0, 0, -1)).with_hero_wavelengths(hero);
+
+    let sample = metal.sample(&r_in, &rec).unwrap();
+    assert!(sample.is_spectral);
+    let metal_ior = fresnel::gulbrandsen(0.9, 0.9);
+    let expected = hero
+        .wavelengths()
+        .map(|wavelength| fresnel::thin_film(1., 1., 1.4, metal_ior, 300., wavelength));
+    assert_close(sample.f.x(), expected[0]);
+    assert_close(sample.f.y(), expected[1]);
+    assert_close(sample.f.z(), expected[2]);
+}
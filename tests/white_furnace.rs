@@ -0,0 +1,239 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use raytracing::{
+    material::{
+        Coated, Conductor, Dielectric, Lambertian, LambertianTransmission, Mix, OrenNayar,
+        Principled, Sheen,
+    },
+    texture::SolidColor,
+    Color, HitRecord, Material, Point3, Ray, Vec3,
+};
+
+const SAMPLES: usize = 100_000;
+
+/// A surface facing up at the origin, with its tangents along x and y, seen from `theta`
+/// degrees off its normal toward x.
+fn setup(theta: f64) -> (HitRecord, Ray) {
+    let rec = HitRecord {
+        normal: Vec3::from(0, 0, 1),
+        dpdu: Vec3::from(1, 0, 0),
+        dpdv: Vec3::from(0, 1, 0),
+        front_face: true,
+        ..Default::default()
+    };
+    let wo = Vec3::from(theta.to_radians().sin(), 0, theta.to_radians().cos());
+    (rec, Ray::new(Point3::from(0, 0, 0) + wo, -wo))
+}
+
+/// Mean and standard error of the red channel of `SAMPLES` colors.
+fn estimate(mut sample: impl FnMut() -> Color) -> (f64, f64) {
+    let mut sum = 0.;
+    let mut sum_squares = 0.;
+    for _ in 0..SAMPLES {
+        let value = sample().r();
+        sum += value;
+        sum_squares += value * value;
+    }
+    let n = SAMPLES as f64;
+    let mean = sum / n;
+    let variance = (sum_squares / n - mean * mean).max(0.);
+    (mean, (variance / n).sqrt())
+}
+
+/// The fraction of the light reflected or transmitted by the surface, estimated by importance
+/// sampling the BSDF.
+fn sampled_albedo(material: &dyn Material, theta: f64) -> (f64, f64) {
+    let (rec, r_in) = setup(theta);
+    let wo = -r_in.direction();
+    estimate(|| {
+        let Some(sample) = material.sample(&r_in, &rec) else {
+            return Color::new();
+        };
+        if !sample.is_delta {
+            let wi = sample.scattered.direction().unit_vector();
+            // Sharp lobes have large densities, so they are compared relative to their size.
+            let pdf = material.pdf(&rec, wo, wi);
+            assert!(
+                (pdf - sample.pdf).abs() < 1e-9 * pdf.max(1.),
+                "{pdf} != {}",
+                sample.pdf
+            );
+            let f = material.eval(&rec, wo, wi);
+            assert!(
+                ((f - sample.f) / f.r().max(1.)).near_zero(),
+                "{f} != {}",
+                sample.f
+            );
+        }
+        sample.weight(rec.normal)
+    })
+}
+
+/// The part of the sampled fraction coming from delta lobes, which `eval` can't see.
+fn delta_albedo(material: &dyn Material, theta: f64) -> (f64, f64) {
+    let (rec, r_in) = setup(theta);
+    estimate(|| match material.sample(&r_in, &rec) {
+        Some(sample) if sample.is_delta => sample.weight(rec.normal),
+        _ => Color::new(),
+    })
+}
+
+/// The same fraction, estimated by integrating the BSDF over uniformly picked directions.
+fn integrated_albedo(material: &dyn Material, theta: f64) -> (f64, f64) {
+    let (rec, r_in) = setup(theta);
+    let wo = -r_in.direction();
+    estimate(|| {
+        let wi = Vec3::random_unit_vector();
+        material.eval(&rec, wo, wi) * wi.dot(rec.normal).abs() * 4. * PI
+    })
+}
+
+/// Check that sampling the material is consistent with its BSDF, and that it reflects a
+/// fraction of the light within `expected` when lit by a uniform white environment. Return
+/// that fraction for each angle, from the normal to grazing.
+fn assert_furnace(material: &dyn Material, expected: std::ops::RangeInclusive<f64>) -> [f64; 4] {
+    [0., 30., 60., 85.].map(|theta| {
+        let (sampled, sampled_error) = sampled_albedo(material, theta);
+        let (integrated, integrated_error) = integrated_albedo(material, theta);
+        let (delta, delta_error) = delta_albedo(material, theta);
+        let integrated = integrated + delta;
+        // Estimates farther apart than six standard errors are all but impossible by chance.
+        let tolerance =
+            6. * (sampled_error.powi(2) + integrated_error.powi(2) + delta_error.powi(2)).sqrt();
+        assert!(
+            (sampled - integrated).abs() <= tolerance,
+            "{theta}: {sampled} != {integrated}"
+        );
+        let margin = 6. * sampled_error;
+        assert!(
+            sampled + margin >= *expected.start() && sampled - margin <= *expected.end(),
+            "{theta}: {sampled}"
+        );
+        sampled
+    })
+}
+
+#[test]
+fn smooth_oren_nayar_is_lambertian() {
+    assert_furnace(&OrenNayar::new(Color::from(1, 1, 1), 0.), 0.99..=1.01);
+}
+
+#[test]
+fn rough_oren_nayar_does_not_create_energy() {
+    for sigma in [15., 30., 45.] {
+        assert_furnace(&OrenNayar::new(Color::from(1, 1, 1), sigma), 0.6..=1.01);
+    }
+}
+
+#[test]
+fn sheen_does_not_create_energy() {
+    for roughness in [0.1, 0.5, 1.] {
+        let albedo = assert_furnace(&Sheen::new(Color::from(1, 1, 1), roughness), 0.0..=1.);
+        // Sheen shows at grazing angles: it reflects more light the more grazing the view,
+        // and a large share of it at the most grazing angle.
+        assert!(albedo.windows(2).all(|w| w[0] < w[1]), "{albedo:?}");
+        assert!(albedo[3] > 0.4, "{albedo:?}");
+    }
+}
+
+#[test]
+fn lambertian_transmission_conserves_energy() {
+    let half = Color::from(0.5, 0.5, 0.5);
+    let material = LambertianTransmission::new(half, half);
+    assert_furnace(&material, 0.99..=1.01);
+
+    // Half of the light goes through the sheet.
+    let (rec, r_in) = setup(30.);
+    let through = (0..SAMPLES)
+        .filter_map(|_| material.sample(&r_in, &rec))
+        .filter(|sample| sample.scattered.direction().dot(rec.normal) < 0.)
+        .count();
+    let ratio = through as f64 / SAMPLES as f64;
+    assert!((ratio - 0.5).abs() < 0.01, "{ratio}");
+}
+
+#[test]
+fn metallic_principled_with_a_clearcoat_does_not_create_energy() {
+    let mut material = Principled::new(Color::from(1, 1, 1));
+    material.metallic = Arc::new(SolidColor::gray(1.));
+    material.clearcoat = Arc::new(SolidColor::gray(1.));
+    // A glossy coat is too sharp for the uniform estimate to find.
+    material.clearcoat_gloss = Arc::new(SolidColor::gray(0.));
+    for roughness in [0.2, 0.5, 1.] {
+        material.roughness = Arc::new(SolidColor::gray(roughness));
+        assert_furnace(&material, 0. ..=1.);
+    }
+}
+
+#[test]
+fn transmissive_principled_does_not_create_energy() {
+    let mut material = Principled::new(Color::from(1, 1, 1));
+    material.transmission = Arc::new(SolidColor::gray(1.));
+    // Smoother glass is too sharp for the uniform estimate to find.
+    for roughness in [0.5, 1.] {
+        material.roughness = Arc::new(SolidColor::gray(roughness));
+        assert_furnace(&material, 0. ..=1.);
+    }
+    // Glass matching the outside lets every ray through untouched.
+    material.ior = 1.;
+    assert_furnace(&material, 0.99..=1.01);
+    let (rec, r_in) = setup(30.);
+    let sample = material.sample(&r_in, &rec).unwrap();
+    assert!((sample.scattered.direction() - r_in.direction()).near_zero());
+    assert!((sample.weight(rec.normal) - Color::from(1, 1, 1)).near_zero());
+}
+
+#[test]
+fn coated_lambertian_does_not_create_energy() {
+    let white = Arc::new(Lambertian::new(Color::from(1, 1, 1)));
+    for ior in [1.3, 1.5, 2.] {
+        let albedo = assert_furnace(&Coated::new(white.clone(), ior), 0. ..=1.);
+        // The coat only loses the light it reflects back inside toward the base.
+        assert!(albedo[0] > 0.7, "{ior}: {}", albedo[0]);
+    }
+}
+
+#[test]
+fn even_mix_conserves_energy() {
+    let white = Arc::new(Lambertian::new(Color::from(1, 1, 1)));
+    let mixes = [
+        Mix::new(
+            white.clone(),
+            Arc::new(OrenNayar::new(Color::from(1, 1, 1), 0.)),
+            0.5,
+        ),
+        Mix::new(white.clone(), Arc::new(Dielectric::new(1.5)), 0.5),
+    ];
+    for mix in mixes {
+        assert_furnace(&mix, 0.99..=1.01);
+    }
+}
+
+/// A nearly perfect reflector, so that the furnace shows the energy lost by the microfacets.
+fn white_conductor(roughness_u: f64, roughness_v: f64) -> Conductor {
+    let eta = Color::from(0.2, 0.2, 0.2);
+    let k = Color::from(10, 10, 10);
+    Conductor::anisotropic(eta, k, roughness_u, roughness_v)
+}
+
+#[test]
+fn anisotropic_conductor_does_not_create_energy() {
+    for (roughness_u, roughness_v) in [(0.3, 0.7), (0.7, 0.3)] {
+        assert_furnace(&white_conductor(roughness_u, roughness_v), 0. ..=1.);
+    }
+}
+
+#[test]
+fn anisotropic_highlights_stretch_along_the_rougher_tangent() {
+    let metal = white_conductor(0.7, 0.2);
+    let (rec, r_in) = setup(0.);
+    let (mut spread_u, mut spread_v) = (0., 0.);
+    for _ in 0..10_000 {
+        if let Some(sample) = metal.sample(&r_in, &rec) {
+            let wi = sample.scattered.direction().unit_vector();
+            spread_u += wi.dot(rec.dpdu).abs();
+            spread_v += wi.dot(rec.dpdv).abs();
+        }
+    }
+    assert!(spread_u > 2. * spread_v, "{spread_u} {spread_v}");
+}